@@ -2,7 +2,11 @@ extern crate corpuslib;
 extern crate time;
 
 
-use std::collections::{HashMap, HashSet};
+use corpuslib::coocs::CoocCounter;
+use corpuslib::stream::LineStreamer;
+use corpuslib::tokenize::{Normalization, Normalized, WhitespaceTokenizer};
+use corpuslib::vocab::vocabulary::DEFAULT_UNKNOWN;
+use corpuslib::vocab::Vocabulary;
 use time::precise_time_ns;
 
 
@@ -16,35 +20,21 @@ const NF: usize = 4;
 
 
 fn main() {
-    // Count frequencies of words.
-    let t1 = precise_time_ns();
-    let mut freqs: HashMap<String, usize> = HashMap::new();
-    for line in corpuslib::stream::LineStreamer::new(&DIRECTORY) {
-        for word in line.split_whitespace() {
-            let freq = freqs.entry(word.to_string()).or_insert(0);
-            *freq += 1;
-        }
-    }
-    let t2 = precise_time_ns();
-    println!("Frequencies of {} word types computed ({} ns).", freqs.len(), t2 - t1);
+    // Split lines on whitespace and lowercase the words, for both the vocabulary and the co-occurrence counts.
+    let lowercase = Normalization { lowercase: true, ..Normalization::default() };
+    let tokenizer = Normalized::new(WhitespaceTokenizer, lowercase);
 
-    // Vocabulary is words that have occurred MIN_FREQ or more times.
+    // Count frequencies of words and retain those that have occurred MIN_FREQ or more times.
     let t1 = precise_time_ns();
-    let mut vocabulary: HashSet<String> = HashSet::new();
-    for (word, freq) in &freqs {
-        if *freq >= MIN_FREQ { vocabulary.insert(word.to_string()); }
-    }
+    let vocabulary = Vocabulary::from_lines(LineStreamer::new(&DIRECTORY), &tokenizer, MIN_FREQ, None, DEFAULT_UNKNOWN);
     let t2 = precise_time_ns();
-    println!("{} word types with frequency >= {} retained in word set ({} ns)", vocabulary.len(), MIN_FREQ, t2 - t1);
+    println!("{} word types with frequency >= {} retained in vocabulary ({} ns)", vocabulary.len() - 1, MIN_FREQ, t2 - t1);
 
     // Count co-occurrences and write to CSV.
     let t1 = precise_time_ns();
-    let mut cooc_counter = corpuslib::coocs::CoocCounter::new(NB, NF);
-    for line in corpuslib::stream::LineStreamer::new(&DIRECTORY) {
-        for mut word in line.split_whitespace() {
-            if !vocabulary.contains(word) { word = "<UNKNOWN>" }
-            cooc_counter.register(&word);
-        }
+    let mut cooc_counter = CoocCounter::with_vocabulary(NB, NF, vocabulary);
+    for line in LineStreamer::new(&DIRECTORY) {
+        cooc_counter.register_line(&line, &tokenizer);
     }
     cooc_counter.to_csv("coocs.csv");
     let t2 = precise_time_ns();
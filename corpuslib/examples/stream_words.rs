@@ -1,15 +1,19 @@
 extern crate corpuslib;
 
 
+use corpuslib::tokenize::{Tokenizer, UnicodeWordTokenizer};
+
+
 fn main() {
     // Directory containing a number of corpus text files to be crawled (files can be nested).
     let directory = "/Users/yarlett/Desktop/data/one-billion/training-monolingual.tokenized.shuffled";
 
-    // Create line streamer and stream and count words in files.
+    // Create line streamer and stream and count words in files, splitting lines at Unicode word boundaries.
     let line_streamer = corpuslib::stream::LineStreamer::new(&directory);
+    let tokenizer = UnicodeWordTokenizer;
     let mut n = 0;
     for line in line_streamer {
-        for word in line.split_whitespace() {
+        for word in tokenizer.tokenize(&line) {
             println!("{}", word);
             n += 1;
         }
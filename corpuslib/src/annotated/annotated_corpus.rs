@@ -0,0 +1,287 @@
+// Corpora in which every position carries several parallel attributes, such as word, lemma and part of speech. Each
+// layer is indexed as a Corpus of its own over the same positions and documents, so a run of constraints on one layer
+// is an ordinary suffix array search. A combined index over whole tokens (every layer at once) can be added for
+// queries that pin tokens down completely.
+
+use std::io;
+
+use annotated::query::Query;
+use corpus::corpus::{Corpus, SearchError};
+
+
+pub struct AnnotatedCorpus {
+    pub layer_names: Vec<String>,
+    pub layers: Vec<Corpus>,
+    pub combined: Option<Corpus<Vec<String>>>,
+}
+
+
+// A run of consecutive query tokens that can be looked up in one index: a layer, or the combined index (None).
+struct Anchor {
+    layer: Option<usize>,
+    offset: usize,
+    seq: Vec<usize>,
+}
+
+
+impl AnnotatedCorpus {
+    // Builds a corpus from documents of tokens, each token giving one value per layer in the order of layer_names.
+    // Returns an InvalidInput error if a token has the wrong number of values.
+    pub fn from_documents(layer_names: &[&str], documents: Vec<Vec<Vec<String>>>) -> io::Result<AnnotatedCorpus> {
+        let mut layer_documents: Vec<Vec<Vec<String>>> = vec![Vec::with_capacity(documents.len()); layer_names.len()];
+        for document in documents.iter() {
+            for layer in layer_documents.iter_mut() {
+                layer.push(Vec::with_capacity(document.len()));
+            }
+            for token in document.iter() {
+                if token.len() != layer_names.len() {
+                    let message = format!("Token {:?} does not have {} layers.", token, layer_names.len());
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+                }
+                for (l, value) in token.iter().enumerate() {
+                    layer_documents[l].last_mut().unwrap().push(value.clone());
+                }
+            }
+        }
+        Ok(AnnotatedCorpus {
+            layer_names: layer_names.iter().map(|name| name.to_string()).collect(),
+            layers: layer_documents.into_iter().map(Corpus::from_documents).collect(),
+            combined: None,
+        })
+    }
+
+    // Parses tab-separated vertical text: one token per line with a column per layer, and blank lines between
+    // documents (or sentences).
+    pub fn from_vertical(layer_names: &[&str], text: &str) -> io::Result<AnnotatedCorpus> {
+        let mut documents: Vec<Vec<Vec<String>>> = vec![Vec::new()];
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                if !documents.last().unwrap().is_empty() {
+                    documents.push(Vec::new());
+                }
+                continue;
+            }
+            documents.last_mut().unwrap().push(line.split('\t').map(|value| value.to_string()).collect());
+        }
+        if documents.len() > 1 && documents.last().unwrap().is_empty() {
+            documents.pop();
+        }
+        AnnotatedCorpus::from_documents(layer_names, documents)
+    }
+
+    // Builds the combined index, whose tokens are the values of every layer at a position.
+    pub fn build_combined_index(&mut self) {
+        if self.layers.is_empty() {
+            return;
+        }
+        let documents: Vec<Vec<Vec<String>>> = (0..self.layers[0].num_documents())
+            .map(|doc| (0..self.layers[0].document(doc).len())
+                .map(|i| self.token(self.layers[0].documents[doc] + i).iter().map(|v| v.to_string()).collect())
+                .collect())
+            .collect();
+        self.combined = Some(Corpus::from_token_documents(documents));
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.first().map_or(0, |layer| layer.sequence.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&Corpus> {
+        self.layer_names.iter().position(|n| n == name).map(|l| &self.layers[l])
+    }
+
+    // Returns the value of every layer at a sequence position.
+    pub fn token(&self, seq_pos: usize) -> Vec<&str> {
+        self.layers.iter().map(|layer| layer.stringmap.strings[layer.sequence[seq_pos]].as_str()).collect()
+    }
+
+    // Returns the start positions of every match of a query, in corpus order. Matches never span documents. The
+    // search is anchored on whichever run of query tokens, looked up in a single layer or in the combined index,
+    // occurs least often; the remaining constraints are then checked at each candidate position.
+    pub fn search(&self, query: &Query) -> Result<Vec<usize>, SearchError> {
+        let constraints = self.encode(query)?;
+        let anchors = self.anchors(&constraints);
+        let mut best: Option<(usize, usize, &Anchor)> = None;
+        for anchor in anchors.iter() {
+            let found = match anchor.layer {
+                Some(l) => self.layers[l].search_binary(&anchor.seq),
+                None => self.combined.as_ref().unwrap().search_binary(&anchor.seq),
+            };
+            let (suffix_lo, suffix_hi) = match found {
+                Ok(range) => range,
+                Err(_) => return Ok(Vec::new()),
+            };
+            let better = match best {
+                Some((lo, hi, _)) => suffix_hi - suffix_lo < hi - lo,
+                None => true,
+            };
+            if better {
+                best = Some((suffix_lo, suffix_hi, anchor));
+            }
+        }
+        let mut positions: Vec<usize> = Vec::new();
+        let candidates: Vec<usize> = match best {
+            Some((suffix_lo, suffix_hi, anchor)) => {
+                let offset = anchor.offset;
+                let suffix = match anchor.layer {
+                    Some(l) => &self.layers[l].suffix,
+                    None => &self.combined.as_ref().unwrap().suffix,
+                };
+                suffix[suffix_lo..(suffix_hi + 1)].iter().filter(|&&p| p >= offset).map(|&p| p - offset).collect()
+            },
+            // Only wildcards: every position is a candidate.
+            None => (0..self.len()).collect(),
+        };
+        for start in candidates {
+            if self.matches(&constraints, start) {
+                positions.push(start);
+            }
+        }
+        positions.sort();
+        Ok(positions)
+    }
+
+    // Parses and runs a query written in bracket notation.
+    pub fn search_str(&self, query: &str) -> Result<Vec<usize>, SearchError> {
+        self.search(&Query::parse(query)?)
+    }
+
+    // Encodes each query token as a list of (layer, code) tests.
+    fn encode(&self, query: &Query) -> Result<Vec<Vec<(usize, usize)>>, SearchError> {
+        let mut constraints: Vec<Vec<(usize, usize)>> = Vec::with_capacity(query.tokens.len());
+        let mut unknown: Vec<String> = Vec::new();
+        for token in query.tokens.iter() {
+            let mut tests: Vec<(usize, usize)> = Vec::with_capacity(token.attributes.len());
+            for (name, value) in token.attributes.iter() {
+                let l = match self.layer_names.iter().position(|n| n == name) {
+                    Some(l) => l,
+                    None => return Err(SearchError::InvalidPattern(format!("Unknown layer {}.", name))),
+                };
+                match self.layers[l].stringmap.get(value.as_str()) {
+                    Some(&code) => tests.push((l, code)),
+                    None => unknown.push(value.clone()),
+                }
+            }
+            constraints.push(tests);
+        }
+        if !unknown.is_empty() {
+            return Err(SearchError::UnknownWords(unknown));
+        }
+        Ok(constraints)
+    }
+
+    // Returns the maximal runs of consecutive query tokens constrained on each layer, and, if there is a combined
+    // index, the runs of tokens constrained on every layer.
+    fn anchors(&self, constraints: &[Vec<(usize, usize)>]) -> Vec<Anchor> {
+        let mut anchors: Vec<Anchor> = Vec::new();
+        for l in 0..self.layers.len() {
+            let code_at = |i: usize| constraints[i].iter().find(|&&(layer, _)| layer == l).map(|&(_, code)| code);
+            runs(constraints.len(), code_at, |offset, seq| {
+                anchors.push(Anchor { layer: Some(l), offset: offset, seq: seq });
+            });
+        }
+        if let Some(ref combined) = self.combined {
+            let code_at = |i: usize| {
+                let mut values: Vec<String> = Vec::with_capacity(self.layers.len());
+                for l in 0..self.layers.len() {
+                    let &(_, code) = constraints[i].iter().find(|&&(layer, _)| layer == l)?;
+                    values.push(self.layers[l].stringmap.strings[code].clone());
+                }
+                // A combination that never occurs can be given a code no token has, so the search finds nothing.
+                Some(combined.stringmap.get(&values).cloned().unwrap_or(usize::MAX))
+            };
+            runs(constraints.len(), code_at, |offset, seq| {
+                anchors.push(Anchor { layer: None, offset: offset, seq: seq });
+            });
+        }
+        anchors
+    }
+
+    // Checks every constraint of a query against the tokens starting at a position.
+    fn matches(&self, constraints: &[Vec<(usize, usize)>], start: usize) -> bool {
+        if constraints.is_empty() || start >= self.len() {
+            return false;
+        }
+        if start + constraints.len() > self.layers[0].document_end(start) {
+            return false;
+        }
+        constraints.iter().enumerate().all(|(i, tests)| {
+            tests.iter().all(|&(l, code)| self.layers[l].sequence[start + i] == code)
+        })
+    }
+}
+
+
+// Calls emit with the offset and codes of each maximal run of positions in 0..n for which code_at gives a code.
+fn runs<C: Fn(usize) -> Option<usize>, E: FnMut(usize, Vec<usize>)>(n: usize, code_at: C, mut emit: E) {
+    let mut seq: Vec<usize> = Vec::new();
+    for i in 0..(n + 1) {
+        match if i < n { code_at(i) } else { None } {
+            Some(code) => seq.push(code),
+            None => {
+                if !seq.is_empty() {
+                    emit(i - seq.len(), seq);
+                    seq = Vec::new();
+                }
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use annotated::query::TokenConstraint;
+
+    const TEXT: &str = "We\twe\tPRP\nmade\tmake\tVBD\na\ta\tDT\ncake\tcake\tNN\n.\t.\t.\n\n\
+                        They\tthey\tPRP\nmake\tmake\tVBP\nthe\tthe\tDT\nrules\trule\tNNS\n\n\
+                        Makes\tmake\tVBZ\nthe\tthe\tDT\nday\tday\tNN\n";
+
+    #[test]
+    fn check_layered_search() {
+        let mut c = AnnotatedCorpus::from_vertical(&["word", "lemma", "pos"], TEXT).unwrap();
+        assert_eq!(c.len(), 12);
+        assert_eq!(c.layers[0].documents, vec![0, 5, 9]);
+        assert_eq!(c.token(1), vec!["made", "make", "VBD"]);
+        assert_eq!(c.search_str(r#"[lemma="make"] [pos="DT"] [pos="NN"]"#), Ok(vec![1, 9]));
+        assert_eq!(c.search_str(r#"[lemma="make"] [] [pos="NNS"]"#), Ok(vec![6]));
+        assert_eq!(c.search_str(r#"[pos="NN"] []"#), Ok(vec![3]));
+        // "." ends the first document, so it cannot be followed by "They".
+        assert_eq!(c.search_str(r#"[pos="."] [word="They"]"#), Ok(vec![]));
+        assert_eq!(c.search_str(r#"[lemma="bake"]"#), Err(SearchError::UnknownWords(vec!["bake".to_string()])));
+        match c.search_str(r#"[tag="NN"]"#) {
+            Err(SearchError::InvalidPattern(_)) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        let expected: Vec<Result<Vec<usize>, SearchError>> = [
+            r#"[word="the" & lemma="the" & pos="DT"] [pos="NN"]"#,
+            r#"[word="the" & lemma="the" & pos="NN"]"#,
+            r#"[] [word="a" & lemma="a" & pos="DT"] []"#,
+        ].iter().map(|q| c.search_str(q)).collect();
+        assert_eq!(expected, vec![Ok(vec![10]), Ok(vec![]), Ok(vec![1])]);
+        // The combined index gives the same answers.
+        c.build_combined_index();
+        let combined: Vec<Result<Vec<usize>, SearchError>> = [
+            r#"[word="the" & lemma="the" & pos="DT"] [pos="NN"]"#,
+            r#"[word="the" & lemma="the" & pos="NN"]"#,
+            r#"[] [word="a" & lemma="a" & pos="DT"] []"#,
+        ].iter().map(|q| c.search_str(q)).collect();
+        assert_eq!(combined, expected);
+        assert!(AnnotatedCorpus::from_vertical(&["word", "pos"], "a\tDT\nb\n").is_err());
+    }
+
+    #[test]
+    fn builds_from_token_documents() {
+        let token = |w: &str, p: &str| vec![w.to_string(), p.to_string()];
+        let documents = vec![vec![token("dogs", "NNS"), token("bark", "VBP")], vec![token("cats", "NNS")]];
+        let c = AnnotatedCorpus::from_documents(&["word", "pos"], documents).unwrap();
+        assert_eq!(c.layer("pos").unwrap().documents, vec![0, 2]);
+        assert_eq!(c.search(&Query::new(vec![TokenConstraint::new(&[("pos", "NNS")])])), Ok(vec![0, 2]));
+    }
+}
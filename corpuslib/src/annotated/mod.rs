@@ -0,0 +1,5 @@
+pub mod annotated_corpus;
+pub mod query;
+
+pub use self::annotated_corpus::AnnotatedCorpus;
+pub use self::query::{Query, TokenConstraint};
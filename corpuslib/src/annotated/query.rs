@@ -0,0 +1,105 @@
+// Queries over annotated corpora, in the bracket notation used by corpus query tools: one bracketed constraint per
+// token, each a conjunction of layer="value" tests, with [] matching any token. For example
+// [lemma="make"] [pos="DT"] [pos="NN"] or [word="the" & pos="DT"] [] [pos="NN"].
+
+use corpus::corpus::SearchError;
+
+
+// The tests a single token must pass, as (layer name, value) pairs. An empty constraint matches any token.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenConstraint {
+    pub attributes: Vec<(String, String)>,
+}
+
+
+impl TokenConstraint {
+    pub fn new(attributes: &[(&str, &str)]) -> TokenConstraint {
+        TokenConstraint {
+            attributes: attributes.iter().map(|&(layer, value)| (layer.to_string(), value.to_string())).collect(),
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    pub tokens: Vec<TokenConstraint>,
+}
+
+
+impl Query {
+    pub fn new(tokens: Vec<TokenConstraint>) -> Query {
+        Query { tokens: tokens }
+    }
+
+    pub fn parse(text: &str) -> Result<Query, SearchError> {
+        let invalid = |message: &str| SearchError::InvalidPattern(format!("{} in query {}", message, text));
+        let mut tokens: Vec<TokenConstraint> = Vec::new();
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            if !rest.starts_with('[') {
+                return Err(invalid("Expected ["));
+            }
+            rest = rest[1..].trim_start();
+            let mut constraint = TokenConstraint::default();
+            while !rest.starts_with(']') {
+                if !constraint.attributes.is_empty() {
+                    if !rest.starts_with('&') {
+                        return Err(invalid("Expected & or ]"));
+                    }
+                    rest = rest[1..].trim_start();
+                }
+                let name_end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                if name_end == 0 {
+                    return Err(invalid("Expected a layer name"));
+                }
+                let layer = &rest[..name_end];
+                rest = rest[name_end..].trim_start();
+                if !rest.starts_with('=') {
+                    return Err(invalid("Expected ="));
+                }
+                rest = rest[1..].trim_start();
+                if !rest.starts_with('"') {
+                    return Err(invalid("Expected a quoted value"));
+                }
+                let value_end = match rest[1..].find('"') {
+                    Some(end) => end + 1,
+                    None => return Err(invalid("Unterminated value")),
+                };
+                constraint.attributes.push((layer.to_string(), rest[1..value_end].to_string()));
+                rest = rest[(value_end + 1)..].trim_start();
+                if rest.is_empty() {
+                    return Err(invalid("Expected ]"));
+                }
+            }
+            rest = rest[1..].trim_start();
+            tokens.push(constraint);
+        }
+        if tokens.is_empty() {
+            return Err(invalid("Empty query"));
+        }
+        Ok(Query { tokens: tokens })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_query_parsing() {
+        let query = Query::parse(r#"[lemma="make"] [ pos = "DT" & word="a" ][]"#).unwrap();
+        assert_eq!(query, Query::new(vec![
+            TokenConstraint::new(&[("lemma", "make")]),
+            TokenConstraint::new(&[("pos", "DT"), ("word", "a")]),
+            TokenConstraint::default(),
+        ]));
+        for bad in &["", "lemma=\"make\"", "[lemma=make]", "[lemma=\"make\"", "[pos=\"DT\" word=\"a\"]", "[=\"a\"]"] {
+            match Query::parse(bad) {
+                Err(SearchError::InvalidPattern(_)) => {},
+                other => panic!("{:?} parsed as {:?}", bad, other),
+            }
+        }
+    }
+}
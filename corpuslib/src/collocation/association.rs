@@ -0,0 +1,152 @@
+// Association measures for collocations. An n-gram is scored as a pair of parts, its first n - 1 tokens and its last
+// token, using the 2x2 contingency table of how often the parts occur together and apart.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use corpus::corpus::Corpus;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Measure {
+    // Pointwise mutual information, in bits.
+    Pmi,
+    // Dunning's log-likelihood ratio (G²).
+    LogLikelihood,
+    TScore,
+    ChiSquare,
+    Dice,
+}
+
+
+// Observed frequencies for a pair of parts: how often they occur together, how often each occurs at all, and the
+// total number of observations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contingency {
+    pub joint: usize,
+    pub first: usize,
+    pub second: usize,
+    pub total: usize,
+}
+
+
+impl Contingency {
+    pub fn score(&self, measure: Measure) -> f64 {
+        let n = self.total as f64;
+        let o11 = self.joint as f64;
+        let o12 = (self.first - self.joint) as f64;
+        let o21 = (self.second - self.joint) as f64;
+        let o22 = n - o11 - o12 - o21;
+        let (r1, r2) = (o11 + o12, o21 + o22);
+        let (c1, c2) = (o11 + o21, o12 + o22);
+        let e11 = r1 * c1 / n;
+        match measure {
+            Measure::Pmi => (o11 / e11).log2(),
+            Measure::LogLikelihood => {
+                let term = |o: f64, e: f64| if o > 0.0 { o * (o / e).ln() } else { 0.0 };
+                2.0 * (term(o11, e11) + term(o12, r1 * c2 / n) + term(o21, r2 * c1 / n) + term(o22, r2 * c2 / n))
+            },
+            Measure::TScore => (o11 - e11) / o11.sqrt(),
+            Measure::ChiSquare => {
+                let denominator = r1 * r2 * c1 * c2;
+                if denominator > 0.0 { n * (o11 * o22 - o12 * o21).powi(2) / denominator } else { 0.0 }
+            },
+            Measure::Dice => 2.0 * o11 / (r1 + c1),
+        }
+    }
+}
+
+
+// A scored n-gram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collocation<'a> {
+    pub ngram: &'a [usize],
+    pub contingency: Contingency,
+    pub score: f64,
+}
+
+
+pub struct CollocationFinder<'a> {
+    corpus: &'a Corpus,
+    min_freq: usize,
+    unigram_counts: Vec<usize>,
+}
+
+
+impl<'a> CollocationFinder<'a> {
+    // Creates a finder that ignores n-grams occurring fewer than min_freq times.
+    pub fn new(corpus: &'a Corpus, min_freq: usize) -> CollocationFinder<'a> {
+        let mut unigram_counts = vec![0; corpus.stringmap.code_ctr];
+        for code in corpus.sequence.iter() {
+            unigram_counts[*code] += 1;
+        }
+        CollocationFinder { corpus: corpus, min_freq: cmp::max(min_freq, 1), unigram_counts: unigram_counts }
+    }
+
+    // Returns bigrams ranked by an association measure, highest first.
+    pub fn bigrams(&self, measure: Measure) -> Vec<Collocation<'a>> {
+        self.ngrams(2, measure)
+    }
+
+    // Returns n-grams (n >= 2) ranked by an association measure, highest first. Each n-gram is scored as the
+    // association between its first n - 1 tokens and its last token.
+    pub fn ngrams(&self, n: usize, measure: Measure) -> Vec<Collocation<'a>> {
+        let mut collocations: Vec<Collocation<'a>> = Vec::new();
+        if n < 2 {
+            return collocations;
+        }
+        let prefix_counts: HashMap<&[usize], usize> = self.corpus.ngram_counts(n - 1, 1).into_iter().collect();
+        let total = self.corpus.sequence.len();
+        for (ngram, count) in self.corpus.ngram_counts(n, self.min_freq) {
+            let contingency = Contingency {
+                joint: count,
+                first: prefix_counts[&ngram[..(n - 1)]],
+                second: self.unigram_counts[ngram[n - 1]],
+                total: total,
+            };
+            collocations.push(Collocation { ngram: ngram, contingency: contingency, score: contingency.score(measure) });
+        }
+        collocations.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(cmp::Ordering::Equal));
+        collocations
+    }
+
+    // Returns the top k n-grams for a measure, decoded to strings, with their scores.
+    pub fn top_strings(&self, n: usize, measure: Measure, k: usize) -> Vec<(Vec<&'a str>, f64)> {
+        let corpus = self.corpus;
+        self.ngrams(n, measure).iter().take(k).map(|c| (corpus.decode(c.ngram), c.score)).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contingency_scores() {
+        // Two parts that always occur together are strongly associated.
+        let together = Contingency { joint: 10, first: 10, second: 10, total: 1000 };
+        let apart = Contingency { joint: 1, first: 100, second: 100, total: 1000 };
+        for &measure in [Measure::Pmi, Measure::LogLikelihood, Measure::TScore, Measure::ChiSquare, Measure::Dice].iter() {
+            assert!(together.score(measure) > apart.score(measure));
+        }
+        assert!((together.score(Measure::Pmi) - 100f64.log2()).abs() < 1e-9);
+        assert!((together.score(Measure::Dice) - 1.0).abs() < 1e-9);
+        // Independent parts have no association.
+        let independent = Contingency { joint: 10, first: 100, second: 100, total: 1000 };
+        assert!(independent.score(Measure::Pmi).abs() < 1e-9);
+        assert!(independent.score(Measure::LogLikelihood).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ranks_collocations() {
+        let text = "new york is big and new york is old but the city is new and the man is old";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        let finder = CollocationFinder::new(&c, 2);
+        let top = finder.top_strings(2, Measure::Pmi, 1);
+        assert_eq!(top[0].0, vec!["new", "york"]);
+        for collocation in finder.ngrams(3, Measure::LogLikelihood) {
+            assert!(collocation.contingency.joint >= 2);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod association;
+
+pub use self::association::{Collocation, CollocationFinder, Contingency, Measure};
@@ -0,0 +1,161 @@
+use std::cmp;
+
+use corpus::corpus::Corpus;
+
+
+// A keyword-in-context line: a match of the query with the words to its left and right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KwicLine<'a> {
+    pub position: usize,
+    pub left: Vec<&'a str>,
+    pub keyword: Vec<&'a str>,
+    pub right: Vec<&'a str>,
+}
+
+
+// A context word to sort concordance lines by. Left(1) is the word immediately before the match ("1L") and Right(2)
+// the second word after it ("2R").
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Left(usize),
+    Right(usize),
+}
+
+
+pub struct Concordance<'a> {
+    corpus: &'a Corpus,
+    left_width: usize,
+    right_width: usize,
+}
+
+
+impl<'a> Concordance<'a> {
+    pub fn new(corpus: &'a Corpus, left_width: usize, right_width: usize) -> Concordance<'a> {
+        Concordance { corpus: corpus, left_width: left_width, right_width: right_width }
+    }
+
+    // Returns the sequence positions of every match of the query, in corpus order. Queries containing words that are
+    // not in the corpus have no matches.
+    pub fn positions(&self, query: &[&str]) -> Vec<usize> {
+        let mut positions: Vec<usize> = Vec::new();
+        if query.is_empty() {
+            return positions;
+        }
+        if let Ok((suffix_lo, suffix_hi)) = self.corpus.search_strings(query) {
+            positions.extend_from_slice(&self.corpus.suffix[suffix_lo..(suffix_hi + 1)]);
+            positions.sort();
+        }
+        positions
+    }
+
+    // Returns the concordance lines for every match of the query, in corpus order.
+    pub fn lines(&self, query: &[&str]) -> Vec<KwicLine<'a>> {
+        self.page(query, &[], 0, usize::MAX)
+    }
+
+    // Returns the concordance lines for every match of the query, sorted by the context words named in sort_keys
+    // (earlier keys take precedence; ties are left in corpus order).
+    pub fn sorted_lines(&self, query: &[&str], sort_keys: &[SortKey]) -> Vec<KwicLine<'a>> {
+        self.page(query, sort_keys, 0, usize::MAX)
+    }
+
+    // Returns one page of sorted concordance lines. Pages are numbered from zero.
+    pub fn page(&self, query: &[&str], sort_keys: &[SortKey], page: usize, page_size: usize) -> Vec<KwicLine<'a>> {
+        let n = query.len();
+        let mut positions = self.positions(query);
+        // Sort positions before building lines so that only the requested page is decoded.
+        if !sort_keys.is_empty() {
+            positions.sort_by(|a, b| {
+                for key in sort_keys.iter() {
+                    let ordering = self.context_word(*a, n, *key).cmp(&self.context_word(*b, n, *key));
+                    if ordering != cmp::Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                cmp::Ordering::Equal
+            });
+        }
+        let start = cmp::min(page.saturating_mul(page_size), positions.len());
+        let end = cmp::min(start.saturating_add(page_size), positions.len());
+        positions[start..end].iter().map(|position| self.line(*position, n)).collect()
+    }
+
+    // Returns concordance lines for a uniform random sample of up to n matches of the query, in corpus order. The
+    // same seed always gives the same lines.
+    pub fn sample(&self, query: &[&str], n: usize, seed: usize) -> Vec<KwicLine<'a>> {
+        match self.corpus.sample_strings(query, n, seed) {
+            Ok(positions) => positions.iter().map(|position| self.line(*position, query.len())).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // Builds the concordance line for a match of length n at a sequence position. Context stops at the edges of the
+    // document containing the match.
+    fn line(&self, position: usize, n: usize) -> KwicLine<'a> {
+        let offset = self.corpus.document_position(position).1;
+        let left_start = position - cmp::min(offset, self.left_width);
+        let right_end = cmp::min(position + n + self.right_width, self.corpus.document_end(position));
+        KwicLine {
+            position: position,
+            left: self.decode(left_start, position),
+            keyword: self.decode(position, position + n),
+            right: self.decode(position + n, right_end),
+        }
+    }
+
+    // Returns the context word named by a sort key for a match of length n (None beyond the edges of the document).
+    fn context_word(&self, position: usize, n: usize, key: SortKey) -> Option<&'a str> {
+        let offset = self.corpus.document_position(position).1;
+        let seq_pos = match key {
+            SortKey::Left(k) if k >= 1 && k <= offset => position - k,
+            SortKey::Right(k) if k >= 1 && position + n + k - 1 < self.corpus.document_end(position) => position + n + k - 1,
+            _ => return None,
+        };
+        Some(self.corpus.stringmap.strings[self.corpus.sequence[seq_pos]].as_str())
+    }
+
+    fn decode(&self, start: usize, end: usize) -> Vec<&'a str> {
+        let corpus = self.corpus;
+        corpus.decode(&corpus.sequence[start..end])
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use corpus::corpus::Corpus;
+
+    fn corpus_from(text: &str) -> Corpus {
+        Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn lines_in_corpus_order() {
+        let c = corpus_from("the cat sat on the mat by the dog");
+        let concordance = Concordance::new(&c, 2, 1);
+        let lines = concordance.lines(&["the"]);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].position, 0);
+        assert!(lines[0].left.is_empty());
+        assert_eq!(lines[1].left, vec!["sat", "on"]);
+        assert_eq!(lines[1].keyword, vec!["the"]);
+        assert_eq!(lines[1].right, vec!["mat"]);
+        assert_eq!(lines[2].right, vec!["dog"]);
+        assert!(concordance.lines(&["the", "unicorn"]).is_empty());
+    }
+
+    #[test]
+    fn sorting_and_paging() {
+        let c = corpus_from("the cat sat on the mat by the dog");
+        let concordance = Concordance::new(&c, 1, 1);
+        let by_right: Vec<usize> = concordance.sorted_lines(&["the"], &[SortKey::Right(1)]).iter().map(|l| l.position).collect();
+        assert_eq!(by_right, vec![0, 7, 4]);
+        let by_left: Vec<usize> = concordance.sorted_lines(&["the"], &[SortKey::Left(1)]).iter().map(|l| l.position).collect();
+        assert_eq!(by_left, vec![0, 7, 4]);
+        let page = concordance.page(&["the"], &[SortKey::Right(1)], 1, 2);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].position, 4);
+    }
+}
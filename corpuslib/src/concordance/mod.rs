@@ -0,0 +1,3 @@
+pub mod kwic;
+
+pub use self::kwic::{Concordance, KwicLine, SortKey};
@@ -5,10 +5,14 @@ use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::rc::Rc;
 
+use tokenize::Tokenizer;
+use vocab::Vocabulary;
+
 
 pub struct CoocCounter<> {
     events:       HashSet<Rc<String>>,
     freqs:        HashMap<(Rc<String>, Rc<String>), usize>,
+    mapping:      Option<Vocabulary>,
     num_b:        usize,
     num_f:        usize,
     vocabulary:   HashSet<Rc<String>>,
@@ -22,6 +26,7 @@ impl CoocCounter {
         CoocCounter{
             events: HashSet::new(),
             freqs: HashMap::new(),
+            mapping: None,
             num_b: num_b,
             num_f: num_f,
             vocabulary: HashSet::new(),
@@ -30,6 +35,13 @@ impl CoocCounter {
         }
     }
 
+    // Creates a counter that maps words outside the vocabulary to its unknown token before counting them.
+    pub fn with_vocabulary(num_b: usize, num_f: usize, vocabulary: Vocabulary) -> CoocCounter {
+        let mut cooc_counter = CoocCounter::new(num_b, num_f);
+        cooc_counter.mapping = Some(vocabulary);
+        cooc_counter
+    }
+
     pub fn freqs(&self) -> &HashMap<(Rc<String>, Rc<String>), usize> {
         &self.freqs
     }
@@ -55,7 +67,11 @@ impl CoocCounter {
     pub fn register(&mut self, word: &str) {
         // Update window (remove left-most item; insert new item at right-most point).
         if self.window.len() >= self.window_size { self.window.remove(0); }
-        let word_inner = Rc::new(word.to_string());
+        let word = match self.mapping {
+            Some(ref vocabulary) => vocabulary.map(word).to_string(),
+            None => word.to_string(),
+        };
+        let word_inner = Rc::new(word);
         self.vocabulary.insert(word_inner.clone());
         self.window.push(word_inner.clone());
         // Update co-occurrences if window is required length.
@@ -78,6 +94,18 @@ impl CoocCounter {
         }
     }
 
+    // Ends the current sentence (or any other unit) so that no co-occurrence spans it and the next word.
+    pub fn end_sentence(&mut self) {
+        self.window.clear();
+    }
+
+    // Registers each token of a line in turn, as split by a tokenizer.
+    pub fn register_line<T: Tokenizer>(&mut self, line: &str, tokenizer: &T) {
+        for token in tokenizer.tokenize(line) {
+            self.register(&token);
+        }
+    }
+
     pub fn window(&self) -> &Vec<Rc<String>> {
         &self.window
     }
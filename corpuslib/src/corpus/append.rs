@@ -0,0 +1,137 @@
+// Growing a corpus in place. New documents get their own suffix array, which is merged into the existing one, so the
+// cost of an append depends mostly on the size of the batch rather than the size of the corpus.
+
+use std::cmp;
+
+use corpus::corpus::Corpus;
+use corpus::sequence::sequence_compare;
+use corpus::suffix_array::suffix_array_documents;
+
+
+impl Corpus {
+    // Appends documents to the corpus. Existing codes are reused and new strings get new codes; because new codes
+    // are larger than every existing code, the relative order of existing suffixes is unchanged. Each suffix of the
+    // batch is placed by binary search over the existing suffix array and the two arrays are then interleaved, with
+    // LCP values recomputed only where a batch suffix was inserted.
+    pub fn append_documents(&mut self, documents: Vec<Vec<String>>) {
+        let offset = self.sequence.len();
+        // Intern the new tokens and record where each new document starts.
+        let mut batch_starts: Vec<usize> = Vec::with_capacity(documents.len());
+        for strings in documents.iter() {
+            batch_starts.push(self.sequence.len() - offset);
+            self.documents.push(self.sequence.len());
+            for s in strings.iter() {
+                let code = match self.stringmap.get(s) {
+                    Some(&code_value) => code_value,
+                    None => self.stringmap.add(s),
+                };
+                self.sequence.push(code);
+            }
+        }
+        if batch_starts.is_empty() || self.sequence.len() == offset {
+            return;
+        }
+        // Sort the suffixes of the batch on their own.
+        let batch_suffix = suffix_array_documents(&self.sequence[offset..], self.stringmap.code_ctr, &batch_starts);
+        // Find where each batch suffix goes among the existing suffixes. Batch suffixes are visited in sorted order,
+        // so each search can start from the previous insertion point. Ties go after existing suffixes.
+        let mut ranks: Vec<usize> = Vec::with_capacity(batch_suffix.len());
+        let mut lo = 0;
+        for &p in batch_suffix.iter() {
+            let seq_pos = p + offset;
+            let mut hi = self.suffix.len();
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let cmp = sequence_compare(self.document_suffix(self.suffix[mid]), self.document_suffix(seq_pos));
+                if cmp == cmp::Ordering::Greater {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            ranks.push(lo);
+        }
+        // Interleave the two arrays, keeping existing LCP values wherever two existing suffixes stay adjacent.
+        let old_suffix = ::std::mem::take(&mut self.suffix);
+        let old_lcp = ::std::mem::take(&mut self.lcp);
+        let total = old_suffix.len() + batch_suffix.len();
+        let mut suffix: Vec<usize> = Vec::with_capacity(total);
+        let mut lcp: Vec<usize> = Vec::with_capacity(total);
+        let mut previous_is_old = false;
+        let mut b = 0;
+        for o in 0..(old_suffix.len() + 1) {
+            while b < batch_suffix.len() && ranks[b] == o {
+                let seq_pos = batch_suffix[b] + offset;
+                lcp.push(self.common_prefix(suffix.last().cloned(), seq_pos));
+                suffix.push(seq_pos);
+                previous_is_old = false;
+                b += 1;
+            }
+            if o < old_suffix.len() {
+                let seq_pos = old_suffix[o];
+                if previous_is_old {
+                    lcp.push(old_lcp[o]);
+                } else {
+                    lcp.push(self.common_prefix(suffix.last().cloned(), seq_pos));
+                }
+                suffix.push(seq_pos);
+                previous_is_old = true;
+            }
+        }
+        self.suffix = suffix;
+        self.lcp = lcp;
+    }
+
+    // Returns the length of the common prefix of the document suffixes at two positions (0 if there is no first).
+    fn common_prefix(&self, a: Option<usize>, b: usize) -> usize {
+        match a {
+            Some(a) => {
+                let (sa, sb) = (self.document_suffix(a), self.document_suffix(b));
+                sa.iter().zip(sb.iter()).take_while(|&(x, y)| x == y).count()
+            },
+            None => 0,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use corpus::corpus::Corpus;
+    use corpus::lcp::lcp_array;
+    use corpus::sequence::sequence_compare;
+
+    fn random_documents(num_documents: usize, ntypes: usize) -> Vec<Vec<String>> {
+        (0..num_documents).map(|_| {
+            let len = rand::random::<usize>() % 50;
+            (0..len).map(|_| format!("{}", rand::random::<usize>() % ntypes)).collect()
+        }).collect()
+    }
+
+    #[test]
+    fn append_matches_rebuild() {
+        let first = random_documents(5, 4);
+        let second = random_documents(4, 6);
+        let mut c = Corpus::from_documents(first.clone());
+        c.append_documents(second.clone());
+        let mut all = first;
+        all.extend(second);
+        let rebuilt = Corpus::from_documents(all);
+        assert_eq!(c.sequence.len(), rebuilt.sequence.len());
+        assert_eq!(c.documents, rebuilt.documents);
+        // The merged suffix array is sorted and its LCP array matches a full recomputation.
+        for i in 1..c.suffix.len() {
+            let (a, b) = (c.document_suffix(c.suffix[i - 1]), c.document_suffix(c.suffix[i]));
+            assert!(sequence_compare(a, b) != ::std::cmp::Ordering::Greater);
+        }
+        assert_eq!(c.lcp, lcp_array(&c.sequence, &c.suffix, &c.documents));
+        // Searches agree with the rebuilt corpus.
+        for seq_pos in 0..c.sequence.len() {
+            let words = c.decode(&c.sequence[seq_pos..::std::cmp::min(seq_pos + 2, c.document_end(seq_pos))]);
+            let (r1, r2) = (c.search_strings(&words).unwrap(), rebuilt.search_strings(&words).unwrap());
+            assert_eq!(r1.1 - r1.0, r2.1 - r2.0);
+        }
+    }
+}
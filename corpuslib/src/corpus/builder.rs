@@ -0,0 +1,215 @@
+use corpus::corpus::Corpus;
+use corpus::lcp::lcp_array;
+use corpus::stringmap::Stringmap;
+use corpus::suffix_array::suffix_array_documents;
+use stream::LineStreamer;
+use tokenize::{SentenceSplitter, Tokenizer};
+use vocab::Vocabulary;
+
+
+// Builds a corpus incrementally, interning tokens as they arrive so that the input never has to be held in memory as
+// strings. The suffix and LCP arrays are built once all of the input has been added.
+pub struct CorpusBuilder {
+    sequence: Vec<usize>,
+    documents: Vec<usize>,
+    // Whether the current document was added with add_document, in which case it counts as a document even if empty.
+    explicit_document: bool,
+    stringmap: Stringmap,
+    vocabulary: Option<Vocabulary>,
+    tokenizer: Option<Box<dyn Tokenizer>>,
+}
+
+
+impl CorpusBuilder {
+    pub fn new() -> CorpusBuilder {
+        CorpusBuilder {
+            sequence: Vec::new(),
+            documents: vec![0],
+            explicit_document: false,
+            stringmap: Stringmap::new(),
+            vocabulary: None,
+            tokenizer: None,
+        }
+    }
+
+    // Creates a builder whose codes are the vocabulary's ids, mapping out-of-vocabulary tokens to its unknown token.
+    pub fn with_vocabulary(vocabulary: Vocabulary) -> CorpusBuilder {
+        CorpusBuilder {
+            sequence: Vec::new(),
+            documents: vec![0],
+            explicit_document: false,
+            stringmap: vocabulary.stringmap.clone(),
+            vocabulary: Some(vocabulary),
+            tokenizer: None,
+        }
+    }
+
+    // Sets the tokenizer that add_line, add_lines and add_line_streamer use to split lines (whitespace by default).
+    pub fn set_tokenizer<T: Tokenizer + 'static>(&mut self, tokenizer: T) {
+        self.tokenizer = Some(Box::new(tokenizer));
+    }
+
+    // Ends the current document; tokens added after this belong to a new document. Has no effect if the current
+    // document is still empty, so documents are never empty unless added that way explicitly with add_document.
+    pub fn start_document(&mut self) {
+        if self.documents[self.documents.len() - 1] < self.sequence.len() {
+            self.documents.push(self.sequence.len());
+            self.explicit_document = false;
+        }
+    }
+
+    // Adds a token to the current document.
+    pub fn add_token(&mut self, token: &str) {
+        if let Some(ref vocabulary) = self.vocabulary {
+            self.sequence.push(vocabulary.code(token));
+            return;
+        }
+        let code = match self.stringmap.get(token) {
+            Some(&code_value) => code_value,
+            None => self.stringmap.add(token),
+        };
+        self.sequence.push(code);
+    }
+
+    // Adds the tokens of a line to the current document.
+    pub fn add_line(&mut self, line: &str) {
+        if let Some(tokens) = self.tokenizer.as_ref().map(|tokenizer| tokenizer.tokenize(line)) {
+            for token in tokens.iter() {
+                self.add_token(token);
+            }
+        } else {
+            for token in line.split_whitespace() {
+                self.add_token(token);
+            }
+        }
+    }
+
+    // Adds a complete document, which may be empty. A new document is only opened if the current one already has
+    // tokens or was itself added explicitly, so this follows start_document without leaving an empty document between.
+    pub fn add_document<S: AsRef<str>>(&mut self, tokens: &[S]) {
+        if self.explicit_document || self.documents[self.documents.len() - 1] < self.sequence.len() {
+            self.documents.push(self.sequence.len());
+        }
+        self.explicit_document = true;
+        for token in tokens.iter() {
+            self.add_token(token.as_ref());
+        }
+    }
+
+    // Adds the lines from any iterator to the current document.
+    pub fn add_lines<I: Iterator<Item = String>>(&mut self, lines: I) {
+        for line in lines {
+            self.add_line(&line);
+        }
+    }
+
+    // Splits text into sentences and adds each one as a document of its own, so that n-grams and searches never span
+    // two sentences. Tokens added afterwards continue the last sentence until start_document is called.
+    pub fn add_sentences(&mut self, text: &str, splitter: &SentenceSplitter) {
+        for sentence in splitter.split(text) {
+            self.start_document();
+            self.add_line(sentence);
+        }
+    }
+
+    // Adds every line from a line streamer, starting a new document for each file.
+    pub fn add_line_streamer(&mut self, line_streamer: LineStreamer) {
+        let mut line_streamer = line_streamer;
+        let mut file_index = None;
+        while let Some(line) = line_streamer.next() {
+            if file_index != Some(line_streamer.file_index()) {
+                file_index = Some(line_streamer.file_index());
+                self.start_document();
+            }
+            self.add_line(&line);
+        }
+    }
+
+    // Builds the suffix and LCP arrays and returns the finished corpus.
+    pub fn build(self) -> Corpus {
+        let suffix = suffix_array_documents(&self.sequence, self.stringmap.code_ctr, &self.documents);
+        let lcp = lcp_array(&self.sequence, &suffix, &self.documents);
+        Corpus { sequence: self.sequence, suffix: suffix, lcp: lcp, documents: self.documents, stringmap: self.stringmap }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use corpus::corpus::SearchError;
+    use tokenize::{Normalization, Normalized, PunctuationTokenizer, WhitespaceTokenizer};
+
+    #[test]
+    fn builds_same_corpus_as_from_documents() {
+        let mut builder = CorpusBuilder::new();
+        builder.add_line("the cat sat");
+        builder.add_line("on the mat");
+        builder.start_document();
+        builder.start_document();
+        builder.add_lines(vec!["the dog".to_string(), "sat".to_string()].into_iter());
+        let c = builder.build();
+        let documents = vec![
+            "the cat sat on the mat".split_whitespace().map(|s| s.to_string()).collect(),
+            "the dog sat".split_whitespace().map(|s| s.to_string()).collect(),
+        ];
+        let expected = Corpus::from_documents(documents);
+        assert_eq!(c.sequence, expected.sequence);
+        assert_eq!(c.suffix, expected.suffix);
+        assert_eq!(c.lcp, expected.lcp);
+        assert_eq!(c.documents, vec![0, 6]);
+        // Leading empty documents are kept.
+        let mut builder = CorpusBuilder::new();
+        builder.add_document::<&str>(&[]);
+        builder.add_document(&["a", "b"]);
+        assert_eq!(builder.build().documents, vec![0, 0]);
+        // Mixing lines, start_document and add_document does not add phantom empty documents.
+        let mut builder = CorpusBuilder::new();
+        builder.add_line("a b");
+        builder.start_document();
+        builder.add_document(&["c"]);
+        builder.add_document::<&str>(&[]);
+        builder.start_document();
+        builder.add_document(&["d"]);
+        builder.add_line("e");
+        builder.add_document(&["f"]);
+        let c = builder.build();
+        assert_eq!(c.documents, vec![0, 2, 3, 3, 5]);
+        assert_eq!(c.decode(c.document(3)), vec!["d", "e"]);
+    }
+
+    #[test]
+    fn maps_unknown_tokens_with_vocabulary() {
+        let lines = vec!["the cat sat on the mat".to_string(), "the dog sat".to_string()];
+        let vocabulary = Vocabulary::from_lines(lines.clone().into_iter(), &WhitespaceTokenizer, 2, None, "<unk>");
+        let mut builder = CorpusBuilder::with_vocabulary(vocabulary);
+        builder.add_lines(lines.into_iter());
+        let c = builder.build();
+        assert_eq!(c.decode(&c.sequence), vec!["the", "<unk>", "sat", "<unk>", "the", "<unk>", "the", "<unk>", "sat"]);
+        assert_eq!(c.sequence[..3].to_vec(), vec![1, 0, 2]);
+        let (suffix_lo, suffix_hi) = c.search_strings(&["<unk>", "sat"]).unwrap();
+        assert_eq!(suffix_hi - suffix_lo + 1, 2);
+    }
+
+    #[test]
+    fn splits_lines_with_tokenizer() {
+        let mut builder = CorpusBuilder::new();
+        let normalization = Normalization { lowercase: true, ..Normalization::default() };
+        builder.set_tokenizer(Normalized::new(PunctuationTokenizer, normalization));
+        builder.add_line("The cat, the mat.");
+        let c = builder.build();
+        assert_eq!(c.decode(&c.sequence), vec!["the", "cat", ",", "the", "mat", "."]);
+    }
+
+    #[test]
+    fn adds_sentences_as_documents() {
+        let splitter = SentenceSplitter::for_language("en").unwrap();
+        let mut builder = CorpusBuilder::new();
+        builder.add_sentences("The cat sat. Dr. Who ran.", &splitter);
+        builder.add_sentences("It rained", &splitter);
+        let c = builder.build();
+        assert_eq!(c.documents, vec![0, 3, 6]);
+        assert_eq!(c.search_strings(&["sat.", "Dr."]), Err(SearchError::NotFound));
+    }
+}
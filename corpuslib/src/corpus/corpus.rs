@@ -1,48 +1,117 @@
 use std::cmp;
+use std::fmt::Display;
+use std::hash::Hash;
 
-use corpus::sequence::{sequence_compare, sequence_compare_n};
+use corpus::builder::CorpusBuilder;
+use corpus::lcp::lcp_array;
+use corpus::sequence::sequence_compare_n;
 use corpus::stringmap::Stringmap;
+use corpus::suffix_array::suffix_array_documents;
+use stream::LineStreamer;
+use tokenize::{SentenceSplitter, Tokenizer};
+use vocab::Vocabulary;
 
 
-pub struct Corpus {
+// Reasons a string-level search can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchError {
+    // The query contains words that never occur in the corpus.
+    UnknownWords(Vec<String>),
+    // Every word is known but the sequence does not occur.
+    NotFound,
+    // The query could not be parsed as a pattern.
+    InvalidPattern(String),
+}
+
+
+// A suffix array index over a sequence of tokens, interned as codes by the stringmap. Tokens are strings unless
+// another type is given; the methods that only need codes work for any token type.
+pub struct Corpus<T = String> {
     pub sequence: Vec<usize>,
     pub suffix: Vec<usize>,
-    pub stringmap: Stringmap,
+    pub lcp: Vec<usize>,
+    pub documents: Vec<usize>,
+    pub stringmap: Stringmap<T>,
 }
 
 
 impl Corpus {
     pub fn new(strings: Vec<String>) -> Corpus {
-        // Allocate sequence and suffix array.
-        let mut sequence: Vec<usize> = Vec::with_capacity(strings.len());
-        // Assign integers to corpus.
-        let mut stringmap = Stringmap::new();
-        for s in strings.iter() {
-            match stringmap.get(s) {
-                Some(&code_value) => {
-                    sequence.push(code_value);
-                },
-                None => {
-                    let code_value = stringmap.add(&s);
-                    sequence.push(code_value);
-                },
-            }
+        Corpus::from_documents(vec![strings])
+    }
+
+    // Builds a corpus from several documents. N-grams and searches never span the boundary between two documents.
+    pub fn from_documents(documents: Vec<Vec<String>>) -> Corpus {
+        let mut builder = CorpusBuilder::new();
+        for strings in documents.iter() {
+            builder.add_document(strings);
         }
-        // Set suffix array.
-        let mut suffix: Vec<usize> = Vec::with_capacity(sequence.len());
-        for i in 0..sequence.len() {
-            suffix.push(i);
+        builder.build()
+    }
+
+    // Builds a corpus from every line of a line streamer, with one document per file.
+    pub fn from_line_streamer(line_streamer: LineStreamer) -> Corpus {
+        let mut builder = CorpusBuilder::new();
+        builder.add_line_streamer(line_streamer);
+        builder.build()
+    }
+
+    // Builds a corpus from every line of a line streamer, split into tokens by a tokenizer, with one document per file.
+    pub fn from_line_streamer_tokenized<T: Tokenizer + 'static>(line_streamer: LineStreamer, tokenizer: T) -> Corpus {
+        let mut builder = CorpusBuilder::new();
+        builder.set_tokenizer(tokenizer);
+        builder.add_line_streamer(line_streamer);
+        builder.build()
+    }
+
+    // Builds a corpus from every line of a line streamer with one document per sentence, for sources that hold a
+    // paragraph or more on each line.
+    pub fn from_line_streamer_sentences(line_streamer: LineStreamer, splitter: &SentenceSplitter) -> Corpus {
+        let mut builder = CorpusBuilder::new();
+        for line in line_streamer {
+            builder.add_sentences(&line, splitter);
         }
-        {
-            let suffix_ordering = |a: &usize, b: &usize| {
-                sequence_compare(&sequence[*a..], &sequence[*b..])
-            };
-            suffix.sort_by(suffix_ordering);
+        builder.build()
+    }
+
+    // Builds a corpus from a line streamer as from_line_streamer does, using the vocabulary's ids as codes and mapping
+    // out-of-vocabulary words to its unknown token.
+    pub fn from_line_streamer_with_vocabulary(line_streamer: LineStreamer, vocabulary: Vocabulary) -> Corpus {
+        let mut builder = CorpusBuilder::with_vocabulary(vocabulary);
+        builder.add_line_streamer(line_streamer);
+        builder.build()
+    }
+
+    // Decodes ngram counts back to the strings the ngrams were built from.
+    pub fn decode_ngram_counts(&self, counts: &[(&[usize], usize)]) -> Vec<(Vec<&str>, usize)> {
+        let mut decoded = Vec::with_capacity(counts.len());
+        for &(ngram, count) in counts.iter() {
+            decoded.push((self.decode(ngram), count));
         }
-        // Return.
-        Corpus { sequence: sequence, suffix: suffix, stringmap: stringmap }
+        decoded
     }
 
+    // Returns a vector of distinct ngrams of a specified length in the corpus, decoded to strings.
+    pub fn ngram_strings(&self, n: usize) -> Vec<Vec<&str>> {
+        self.ngrams(n).iter().map(|ng| self.decode(ng)).collect()
+    }
+
+    // Decodes a sequence of corpus codes to strings.
+    pub fn decode(&self, seq: &[usize]) -> Vec<&str> {
+        seq.iter().map(|code| self.stringmap.strings[*code].as_str()).collect()
+    }
+
+    // Returns range of suffix array that points to the required sequence of words using binary search.
+    pub fn search_strings(&self, words: &[&str]) -> Result<(usize, usize), SearchError> {
+        match self.stringmap.encode(words) {
+            Ok(seq) => self.search_binary(&seq).map_err(|_| SearchError::NotFound),
+            Err(unknown) => Err(SearchError::UnknownWords(unknown)),
+        }
+    }
+}
+
+
+impl<T> Corpus<T> {
     // Returns the left-most suffix pointer to a sequence using binary search.
     // Also returns a right-most bound for the sequence which can be used to constrain the maximum search.
     fn binary_search_left(&self, seq: &[usize], suffix_min: usize, suffix_max: usize) -> Result<(usize, usize), bool> {
@@ -52,7 +121,7 @@ impl Corpus {
         let mut right_bound = suffix_max;
         while smax > smin {
             let smid = (smin + smax) / 2;
-            let cmp = sequence_compare_n(&self.sequence[self.suffix[smid]..], seq, &n);
+            let cmp = sequence_compare_n(self.document_suffix(self.suffix[smid]), seq, &n);
             // Update the right bound.
             if cmp == cmp::Ordering::Greater && smid < right_bound {
                 right_bound = smid;
@@ -64,7 +133,7 @@ impl Corpus {
                 smax = smid;
             }
         }
-        if smax == smin && sequence_compare_n(&self.sequence[self.suffix[smin]..], seq, &n) == cmp::Ordering::Equal {
+        if smax == smin && sequence_compare_n(self.document_suffix(self.suffix[smin]), seq, &n) == cmp::Ordering::Equal {
             return Ok((smin, right_bound))
         }
         return Err(false);
@@ -79,7 +148,7 @@ impl Corpus {
         let mut left_bound = suffix_min;
         while smax > smin {
             let smid = ((smin + smax) / 2) + 1;
-            let cmp = sequence_compare_n(&self.sequence[self.suffix[smid]..], seq, &n);
+            let cmp = sequence_compare_n(self.document_suffix(self.suffix[smid]), seq, &n);
             // Update the left bound.
             if cmp == cmp::Ordering::Less && smid > left_bound {
                 left_bound = smid;
@@ -91,20 +160,19 @@ impl Corpus {
                 smin = smid;
             }
         }
-        if smax == smin && sequence_compare_n(&self.sequence[self.suffix[smin]..], seq, &n) == cmp::Ordering::Equal {
+        if smax == smin && sequence_compare_n(self.document_suffix(self.suffix[smin]), seq, &n) == cmp::Ordering::Equal {
             return Ok((smax, left_bound))
         }
         return Err(false);
     }
 
-    // Returns a vector of distinct ngrams of a specified length in the corpus.
+    // Returns a vector of distinct ngrams of a specified length in the corpus (ngrams do not span documents).
     pub fn ngrams(&self, n:usize) -> Vec<&[usize]> {
         let mut ngs: Vec<&[usize]> = Vec::new();
         if n >= 1 {
-            let seq_max = self.sequence.len() - n;
             for suf_pos in 0..self.suffix.len() {
                 let seq_pos = self.suffix[suf_pos];
-                if seq_pos <= seq_max {
+                if self.document_end(seq_pos) - seq_pos >= n {
                     let ng = &self.sequence[seq_pos..(seq_pos + n)];
                     let mut add = false;
                     match ngs.last() {
@@ -126,6 +194,80 @@ impl Corpus {
         ngs
     }
 
+    // Returns each distinct ngram of a specified length with its frequency, in suffix array order. Only ngrams
+    // occurring at least min_count times are returned.
+    pub fn ngram_counts(&self, n: usize, min_count: usize) -> Vec<(&[usize], usize)> {
+        self.ngram_counts_range(n, n, min_count)
+    }
+
+    // Returns each distinct ngram with length in n_min..=n_max with its frequency, ordered by length and then by
+    // suffix array order. The suffix array is walked once: occurrences of an ngram are adjacent in it, and adjacent
+    // suffixes belong to the same ngram exactly when their common prefix is at least n long.
+    pub fn ngram_counts_range(&self, n_min: usize, n_max: usize, min_count: usize) -> Vec<(&[usize], usize)> {
+        let mut counts: Vec<(&[usize], usize)> = Vec::new();
+        for (l, ngram_counts) in self.position_counts_by_length(n_min, n_max, min_count).iter().enumerate() {
+            let n = n_min + l;
+            for &(seq_pos, count) in ngram_counts.iter() {
+                counts.push((&self.sequence[seq_pos..(seq_pos + n)], count));
+            }
+        }
+        counts
+    }
+
+    // Returns each distinct ngram of length n that occurs at least min_count times as the sequence position of one
+    // of its occurrences with its frequency, in suffix array order.
+    pub fn ngram_position_counts(&self, n: usize, min_count: usize) -> Vec<(usize, usize)> {
+        self.position_counts_by_length(n, n, min_count).pop().unwrap_or_default()
+    }
+
+    // Counts ngrams for each length in n_min..=n_max as (sequence position, count), indexed by length - n_min.
+    fn position_counts_by_length(&self, n_min: usize, n_max: usize, min_count: usize) -> Vec<Vec<(usize, usize)>> {
+        if n_min < 1 || n_max < n_min {
+            return Vec::new();
+        }
+        let num_lengths = n_max - n_min + 1;
+        // Counted ngrams for each length, as (sequence position, count).
+        let mut counts_by_length: Vec<Vec<(usize, usize)>> = vec![Vec::new(); num_lengths];
+        // The ngram currently being counted for each length.
+        let mut current: Vec<Option<(usize, usize)>> = vec![None; num_lengths];
+        let flush = |counts: &mut Vec<(usize, usize)>, current: &mut Option<(usize, usize)>| {
+            if let Some((seq_pos, count)) = current.take() {
+                if count >= min_count {
+                    counts.push((seq_pos, count));
+                }
+            }
+        };
+        for suf_pos in 0..self.suffix.len() {
+            let seq_pos = self.suffix[suf_pos];
+            let remaining = self.document_end(seq_pos) - seq_pos;
+            for l in 0..num_lengths {
+                let n = n_min + l;
+                if remaining < n {
+                    flush(&mut counts_by_length[l], &mut current[l]);
+                } else if suf_pos > 0 && self.lcp[suf_pos] >= n && current[l].is_some() {
+                    if let Some((_, ref mut count)) = current[l] {
+                        *count += 1;
+                    }
+                } else {
+                    flush(&mut counts_by_length[l], &mut current[l]);
+                    current[l] = Some((seq_pos, 1));
+                }
+            }
+        }
+        for l in 0..num_lengths {
+            flush(&mut counts_by_length[l], &mut current[l]);
+        }
+        counts_by_length
+    }
+
+    // Returns the k most frequent ngrams of a specified length, most frequent first (ties in suffix array order).
+    pub fn top_ngrams(&self, n: usize, k: usize) -> Vec<(&[usize], usize)> {
+        let mut counts = self.ngram_counts(n, 1);
+        counts.sort_by_key(|&(_, count)| cmp::Reverse(count));
+        counts.truncate(k);
+        counts
+    }
+
     // Returns range of suffix array that points to required sequence in corpus using linear search.
     pub fn search_linear(&self, seq: &[usize]) -> Result<(usize, usize), bool> {
         let n = seq.len();
@@ -133,7 +275,7 @@ impl Corpus {
         let mut suffix_lo = 0;
         let mut suffix_hi = 0;
         for suffix_pos in 0..self.suffix.len() {
-            if sequence_compare_n(&self.sequence[self.suffix[suffix_pos]..], seq, &n) == cmp::Ordering::Equal {
+            if sequence_compare_n(self.document_suffix(self.suffix[suffix_pos]), seq, &n) == cmp::Ordering::Equal {
                 if !found || (suffix_pos < suffix_lo) {
                     found = true;
                     suffix_lo = suffix_pos;
@@ -157,7 +299,7 @@ impl Corpus {
         let n = seq.len();
         // Binary search to get initial search location.
         let search_by_suffix_probe = | suffix_value: &usize | {
-            sequence_compare_n(&self.sequence[*suffix_value..], seq, &n)
+            sequence_compare_n(self.document_suffix(*suffix_value), seq, &n)
         };
         let binary_search_result = self.suffix.binary_search_by(search_by_suffix_probe);
         // Act on binary search result.
@@ -186,6 +328,54 @@ impl Corpus {
 }
 
 
+
+impl<T: Hash + Ord + Clone> Corpus<T> {
+    // Builds a corpus over any token type, such as tags or integer ids, without going through strings. Codes are
+    // assigned in token order, so suffixes, and so ngram counts, come out in token order too.
+    pub fn from_token_documents(documents: Vec<Vec<T>>) -> Corpus<T> {
+        let mut types: Vec<&T> = documents.iter().flat_map(|tokens| tokens.iter()).collect();
+        types.sort();
+        types.dedup();
+        let mut stringmap: Stringmap<T> = Stringmap::new();
+        for token in types {
+            stringmap.add(token);
+        }
+        let mut sequence: Vec<usize> = Vec::new();
+        let mut starts: Vec<usize> = Vec::with_capacity(cmp::max(documents.len(), 1));
+        for tokens in documents.iter() {
+            starts.push(sequence.len());
+            sequence.extend(tokens.iter().map(|token| stringmap.map[token]));
+        }
+        if starts.is_empty() {
+            starts.push(0);
+        }
+        let suffix = suffix_array_documents(&sequence, stringmap.code_ctr, &starts);
+        let lcp = lcp_array(&sequence, &suffix, &starts);
+        Corpus { sequence: sequence, suffix: suffix, lcp: lcp, documents: starts, stringmap: stringmap }
+    }
+
+    pub fn from_tokens(tokens: Vec<T>) -> Corpus<T> {
+        Corpus::from_token_documents(vec![tokens])
+    }
+
+    // Returns the range of the suffix array that points to a sequence of tokens. Tokens that never occur are reported
+    // (displayed as strings, in query order) as search_strings reports unknown words.
+    pub fn search_tokens(&self, tokens: &[T]) -> Result<(usize, usize), SearchError> where T: Display {
+        match self.stringmap.encode_tokens(tokens) {
+            Some(seq) => self.search_binary(&seq).map_err(|_| SearchError::NotFound),
+            None => {
+                let unknown = tokens.iter().filter(|token| !self.stringmap.contains(*token));
+                Err(SearchError::UnknownWords(unknown.map(|token| token.to_string()).collect()))
+            },
+        }
+    }
+
+    // Decodes a sequence of corpus codes to tokens.
+    pub fn decode_tokens(&self, seq: &[usize]) -> Vec<&T> {
+        seq.iter().map(|code| &self.stringmap.strings[*code]).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate rand;
@@ -243,6 +433,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn check_ngram_counts() {
+        // Generate random corpus.
+        let (ntypes, ntokens) = (10, 1000);
+        let c = random_corpus(ntypes, ntokens);
+        // Check counts agree with the size of the suffix range found by search.
+        for n in 1..5 {
+            let counts = c.ngram_counts(n, 1);
+            assert!(counts.len() == c.ngrams(n).len());
+            let mut total = 0;
+            for &(ng, count) in counts.iter() {
+                let (lo, hi) = c.search_binary(ng).unwrap();
+                assert!(count == hi - lo + 1);
+                total += count;
+            }
+            assert!(total == ntokens - n + 1);
+        }
+        // Check a range of lengths matches the individual lengths.
+        let mut expected = Vec::new();
+        for n in 2..4 {
+            expected.extend(c.ngram_counts(n, 3));
+        }
+        assert!(c.ngram_counts_range(2, 3, 3) == expected);
+        // Check top ngrams are sorted by frequency.
+        let top = c.top_ngrams(2, 5);
+        assert!(top.len() == 5);
+        for i in 1..top.len() {
+            assert!(top[i - 1].1 >= top[i].1);
+        }
+    }
+
+    #[test]
+    fn check_string_queries() {
+        let words: Vec<String> = "a rose is a rose is a rose".split_whitespace().map(|s| s.to_string()).collect();
+        let c = Corpus::new(words);
+        // Check string searches agree with code searches.
+        let r = c.search_strings(&["a", "rose"]).unwrap();
+        assert!(r.1 - r.0 + 1 == 3);
+        assert!(c.search_strings(&["rose", "a"]) == Err(SearchError::NotFound));
+        assert!(c.search_strings(&["a", "tulip", "daisy"]) == Err(SearchError::UnknownWords(vec!["tulip".to_string(), "daisy".to_string()])));
+        // Check ngrams decode back to their words.
+        let mut bigrams = c.ngram_strings(2);
+        bigrams.sort();
+        assert!(bigrams == vec![vec!["a", "rose"], vec!["is", "a"], vec!["rose", "is"]]);
+    }
+
+    #[test]
+    fn check_document_boundaries() {
+        let documents: Vec<Vec<String>> = ["a b c", "", "c a b", "b"].iter()
+            .map(|d| d.split_whitespace().map(|s| s.to_string()).collect())
+            .collect();
+        let c = Corpus::from_documents(documents);
+        assert!(c.documents == vec![0, 3, 3, 6]);
+        assert!(c.num_documents() == 4);
+        // Check ngrams and searches do not cross documents ("c c" and "b b" only occur across boundaries).
+        assert!(c.ngram_strings(2).len() == 3);
+        assert!(c.search_strings(&["c", "c"]) == Err(SearchError::NotFound));
+        assert!(c.search_strings(&["b", "b"]) == Err(SearchError::NotFound));
+        let counts = c.decode_ngram_counts(&c.ngram_counts(2, 2));
+        assert!(counts == vec![(vec!["a", "b"], 2)]);
+        // Check positions map back to documents.
+        assert!(c.document_position(4) == (2, 1));
+        let b = *c.stringmap.get("b").unwrap();
+        assert!(c.locate(&[b]) == vec![(0, 1), (2, 2), (3, 0)]);
+        // Check searches agree with linear search.
+        for seq_pos in 0..c.sequence.len() {
+            let seq = &c.sequence[seq_pos..c.document_end(seq_pos)];
+            assert!(c.search_linear(seq) == c.search_binary(seq));
+        }
+    }
+
     #[test]
     fn check_suffix_ordering() {
         // Generate random corpus.
@@ -272,4 +533,27 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn check_token_corpus() {
+        let tags = vec!["DT", "NN", "VBD", "DT", "JJ", "NN", "DT", "NN"];
+        let c: Corpus<&str> = Corpus::from_tokens(tags);
+        let (lo, hi) = c.search_tokens(&["DT", "NN"]).unwrap();
+        assert_eq!(hi - lo + 1, 2);
+        assert_eq!(c.search_tokens(&["NN", "NN"]), Err(SearchError::NotFound));
+        let unknown = Err(SearchError::UnknownWords(vec!["RB".to_string(), "UH".to_string()]));
+        assert_eq!(c.search_tokens(&["DT", "RB", "UH"]), unknown);
+        // Token and string searches report unknown words the same way.
+        let c = Corpus::new(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(c.search_tokens(&["a".to_string(), "RB".to_string(), "UH".to_string()]), unknown);
+        assert_eq!(c.search_strings(&["a", "RB", "UH"]), unknown);
+        let ids: Vec<Vec<u32>> = vec![vec![30, 10, 20, 10], vec![10, 20]];
+        let c = Corpus::from_token_documents(ids);
+        let counts: Vec<(Vec<&u32>, usize)> = c.ngram_counts(2, 1).iter()
+            .map(|&(ngram, count)| (c.decode_tokens(ngram), count))
+            .collect();
+        assert_eq!(counts, vec![(vec![&10, &20], 2), (vec![&20, &10], 1), (vec![&30, &10], 1)]);
+        assert_eq!(c.locate(&c.stringmap.encode_tokens(&[10, 20]).unwrap()), vec![(0, 1), (1, 0)]);
+        assert_eq!(c.search_tokens(&[10, 99]), Err(SearchError::UnknownWords(vec!["99".to_string()])));
+    }
 }
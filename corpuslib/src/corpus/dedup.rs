@@ -0,0 +1,130 @@
+// Exact duplicate detection over the suffix and LCP arrays, as used to deduplicate training data. A token is part of
+// a duplicated span if some suffix starting at or before it shares at least k tokens with another suffix, reaching
+// past it. Every copy of a duplicated span is reported, including the first.
+
+use std::cmp;
+
+use corpus::corpus::Corpus;
+use corpus::documents::document_of;
+
+
+impl<T> Corpus<T> {
+    // Returns the (start, end) sequence ranges, half-open and in corpus order, covered by token spans of at least k
+    // tokens that occur more than once. If across_documents is true, a span only counts as duplicated when it also
+    // occurs in a different document, so repetition within a single document is kept.
+    pub fn duplicate_spans(&self, k: usize, across_documents: bool) -> Vec<(usize, usize)> {
+        let n = self.suffix.len();
+        let k = cmp::max(k, 1);
+        if n < 2 {
+            return Vec::new();
+        }
+        // The longest prefix each suffix shares with a suffix before it and after it in the suffix array, restricted
+        // to suffixes from other documents if required. Each suffix's nearest qualifying neighbour on either side
+        // shares the most with it, and LCP(i, j) = min(lcp[i], LCP(i - 1, j)) carries the shared length along runs
+        // of suffixes from the same document.
+        let doc: Vec<usize> = if across_documents {
+            self.suffix.iter().map(|&p| document_of(&self.documents, p)).collect()
+        } else {
+            (0..n).collect()
+        };
+        let mut left = vec![0; n];
+        for i in 1..n {
+            left[i] = if doc[i - 1] != doc[i] { self.lcp[i] } else { cmp::min(self.lcp[i], left[i - 1]) };
+        }
+        let mut right = vec![0; n];
+        for i in (0..(n - 1)).rev() {
+            right[i] = if doc[i + 1] != doc[i] { self.lcp[i + 1] } else { cmp::min(self.lcp[i + 1], right[i + 1]) };
+        }
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for i in 0..n {
+            let length = cmp::max(left[i], right[i]);
+            if length >= k {
+                spans.push((self.suffix[i], self.suffix[i] + length));
+            }
+        }
+        merge_ranges(spans)
+    }
+}
+
+
+impl Corpus {
+    // Returns a stream of the corpus's documents, one line of space-separated tokens each, with the given ranges
+    // removed.
+    pub fn deduplicated<'a>(&'a self, spans: &[(usize, usize)]) -> DedupStream<'a> {
+        DedupStream { corpus: self, spans: merge_ranges(spans.to_vec()), span: 0, doc: 0 }
+    }
+}
+
+
+// Yields each document of a corpus as a line of text, skipping removed ranges. Documents that are removed entirely
+// still yield an empty line so that line numbers match document ids.
+pub struct DedupStream<'a> {
+    corpus: &'a Corpus,
+    spans: Vec<(usize, usize)>,
+    span: usize,
+    doc: usize,
+}
+
+
+impl<'a> Iterator for DedupStream<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let documents = &self.corpus.documents;
+        if self.doc >= documents.len() {
+            return None;
+        }
+        let start = documents[self.doc];
+        let end = if self.doc + 1 < documents.len() { documents[self.doc + 1] } else { self.corpus.sequence.len() };
+        let mut words: Vec<&str> = Vec::new();
+        let mut i = start;
+        while i < end {
+            while self.span < self.spans.len() && self.spans[self.span].1 <= i {
+                self.span += 1;
+            }
+            if self.span < self.spans.len() && self.spans[self.span].0 <= i {
+                i = self.spans[self.span].1;
+                continue;
+            }
+            words.push(&self.corpus.stringmap.strings[self.corpus.sequence[i]]);
+            i += 1;
+        }
+        self.doc += 1;
+        Some(words.join(" "))
+    }
+}
+
+
+// Sorts ranges and merges those that overlap or touch.
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => { last.1 = cmp::max(last.1, end); },
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documents(texts: &[&str]) -> Corpus {
+        Corpus::from_documents(texts.iter().map(|t| t.split_whitespace().map(|s| s.to_string()).collect()).collect())
+    }
+
+    #[test]
+    fn check_duplicate_spans() {
+        let c = documents(&["a b c d e f", "x b c d y", "p q p q p q z"]);
+        // "b c d" occurs in documents 0 and 1; "p q p q" occurs twice within document 2.
+        assert_eq!(c.duplicate_spans(3, false), vec![(1, 4), (7, 10), (11, 17)]);
+        assert_eq!(c.duplicate_spans(3, true), vec![(1, 4), (7, 10)]);
+        assert_eq!(c.duplicate_spans(4, true), vec![]);
+        let lines: Vec<String> = c.deduplicated(&c.duplicate_spans(3, false)).collect();
+        assert_eq!(lines, vec!["a e f", "x y", "z"]);
+    }
+}
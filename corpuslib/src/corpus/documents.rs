@@ -0,0 +1,60 @@
+// Document boundaries within a corpus. Documents are stored as the sequence position at which each one starts, so
+// the first document always starts at 0 and empty documents share a start with the document that follows them.
+
+use corpus::corpus::Corpus;
+
+
+// Returns the id of the document containing a sequence position.
+pub fn document_of(documents: &[usize], seq_pos: usize) -> usize {
+    documents.partition_point(|&start| start <= seq_pos) - 1
+}
+
+
+// Returns the sequence position one past the end of the document containing a sequence position.
+pub fn document_end(documents: &[usize], sequence_len: usize, seq_pos: usize) -> usize {
+    let doc = document_of(documents, seq_pos);
+    if doc + 1 < documents.len() { documents[doc + 1] } else { sequence_len }
+}
+
+
+impl<T> Corpus<T> {
+    pub fn num_documents(&self) -> usize {
+        self.documents.len()
+    }
+
+    // Returns the tokens of a document.
+    pub fn document(&self, doc: usize) -> &[usize] {
+        let start = self.documents[doc];
+        let end = if doc + 1 < self.documents.len() { self.documents[doc + 1] } else { self.sequence.len() };
+        &self.sequence[start..end]
+    }
+
+    // Maps a sequence position to (document id, offset within the document).
+    pub fn document_position(&self, seq_pos: usize) -> (usize, usize) {
+        let doc = document_of(&self.documents, seq_pos);
+        (doc, seq_pos - self.documents[doc])
+    }
+
+    // Returns the sequence position one past the end of the document containing a sequence position.
+    pub fn document_end(&self, seq_pos: usize) -> usize {
+        document_end(&self.documents, self.sequence.len(), seq_pos)
+    }
+
+    // Returns the suffix starting at a sequence position, cut off at the end of its document. Searches compare these
+    // rather than whole suffixes so that matches never run from one document into the next.
+    pub fn document_suffix(&self, seq_pos: usize) -> &[usize] {
+        &self.sequence[seq_pos..self.document_end(seq_pos)]
+    }
+
+    // Returns (document id, offset within the document) for every occurrence of a sequence, in corpus order.
+    pub fn locate(&self, seq: &[usize]) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        if let Ok((suffix_lo, suffix_hi)) = self.search_binary(seq) {
+            for suffix_pos in suffix_lo..(suffix_hi + 1) {
+                positions.push(self.document_position(self.suffix[suffix_pos]));
+            }
+            positions.sort();
+        }
+        positions
+    }
+}
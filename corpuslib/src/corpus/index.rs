@@ -0,0 +1,76 @@
+// A common query interface for the different index backends (in-memory, memory-mapped and FM-index), so callers can
+// switch between them.
+
+use corpus::corpus::{Corpus, SearchError};
+use corpus::persist::MappedCorpus;
+use corpus::stringmap::Stringmap;
+
+
+pub trait SuffixIndex {
+    // Returns the range of the suffix array whose suffixes begin with a sequence. Every backend orders suffixes as
+    // Corpus::suffix does, so ranges are interchangeable between them; an empty sequence matches every suffix.
+    fn search(&self, seq: &[usize]) -> Result<(usize, usize), bool>;
+
+    // Returns the sorted sequence positions at which a sequence occurs.
+    fn positions(&self, seq: &[usize]) -> Vec<usize>;
+
+    fn stringmap(&self) -> &Stringmap;
+
+    // Returns the number of occurrences of a sequence.
+    fn count(&self, seq: &[usize]) -> usize {
+        match self.search(seq) {
+            Ok((lo, hi)) => hi - lo + 1,
+            Err(_) => 0,
+        }
+    }
+
+    // Returns the range of the suffix array whose suffixes begin with a sequence of words.
+    fn search_words(&self, words: &[&str]) -> Result<(usize, usize), SearchError> {
+        match self.stringmap().encode(words) {
+            Ok(seq) => self.search(&seq).map_err(|_| SearchError::NotFound),
+            Err(unknown) => Err(SearchError::UnknownWords(unknown)),
+        }
+    }
+}
+
+
+impl SuffixIndex for Corpus {
+    fn search(&self, seq: &[usize]) -> Result<(usize, usize), bool> {
+        self.search_binary(seq)
+    }
+
+    fn positions(&self, seq: &[usize]) -> Vec<usize> {
+        sorted_positions(&self.suffix, self.search_binary(seq))
+    }
+
+    fn stringmap(&self) -> &Stringmap {
+        &self.stringmap
+    }
+}
+
+
+impl SuffixIndex for MappedCorpus {
+    fn search(&self, seq: &[usize]) -> Result<(usize, usize), bool> {
+        self.search_binary(seq)
+    }
+
+    fn positions(&self, seq: &[usize]) -> Vec<usize> {
+        sorted_positions(self.suffix(), self.search_binary(seq))
+    }
+
+    fn stringmap(&self) -> &Stringmap {
+        &self.stringmap
+    }
+}
+
+
+fn sorted_positions(suffix: &[usize], range: Result<(usize, usize), bool>) -> Vec<usize> {
+    match range {
+        Ok((lo, hi)) => {
+            let mut positions = suffix[lo..(hi + 1)].to_vec();
+            positions.sort();
+            positions
+        },
+        Err(_) => Vec::new(),
+    }
+}
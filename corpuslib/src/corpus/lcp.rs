@@ -0,0 +1,65 @@
+// Longest-common-prefix array construction in linear time (Kasai et al., 2001).
+
+use corpus::documents::document_end;
+
+
+// Returns the LCP array for a sequence divided into documents and its suffix array. Element i holds the length of the
+// longest common prefix of the suffixes at suffix[i - 1] and suffix[i], where suffixes end at their document
+// boundary; element 0 is always 0.
+pub fn lcp_array(sequence: &[usize], suffix: &[usize], documents: &[usize]) -> Vec<usize> {
+    let n = sequence.len();
+    let mut lcp = vec![0; n];
+    // Inverse of the suffix array: rank[p] is the position of suffix p in the suffix array.
+    let mut rank = vec![0; n];
+    for (i, &p) in suffix.iter().enumerate() {
+        rank[p] = i;
+    }
+    // Visit suffixes in text order; the common prefix shrinks by at most one between consecutive text positions.
+    let mut h: usize = 0;
+    let mut p_end = 0;
+    for p in 0..n {
+        if p == p_end {
+            p_end = document_end(documents, n, p);
+        }
+        if rank[p] > 0 {
+            let q = suffix[rank[p] - 1];
+            let q_end = document_end(documents, n, q);
+            while p + h < p_end && q + h < q_end && sequence[p + h] == sequence[q + h] {
+                h += 1;
+            }
+            lcp[rank[p]] = h;
+            h = h.saturating_sub(1);
+        } else {
+            h = 0;
+        }
+    }
+    lcp
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::*;
+
+    use corpus::suffix_array::suffix_array;
+
+    #[test]
+    fn matches_naive_common_prefixes() {
+        for &ntypes in [1, 2, 5, 50].iter() {
+            let sequence: Vec<usize> = (0..500).map(|_| rand::random::<usize>() % ntypes).collect();
+            let suffix = suffix_array(&sequence, ntypes);
+            let lcp = lcp_array(&sequence, &suffix, &[0]);
+            assert_eq!(lcp[0], 0);
+            for i in 1..suffix.len() {
+                let (a, b) = (&sequence[suffix[i - 1]..], &sequence[suffix[i]..]);
+                let mut h = 0;
+                while h < a.len() && h < b.len() && a[h] == b[h] {
+                    h += 1;
+                }
+                assert_eq!(lcp[i], h);
+            }
+        }
+    }
+}
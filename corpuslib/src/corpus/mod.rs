@@ -1,3 +1,16 @@
+pub mod append;
+pub mod builder;
 pub mod corpus;
+pub mod dedup;
+pub mod documents;
+pub mod index;
+pub mod lcp;
+pub mod pattern;
+pub mod persist;
+pub mod repeats;
+pub mod sample;
 pub mod sequence;
+pub mod skipgrams;
 pub mod stringmap;
+pub mod suffix_array;
+pub mod text;
@@ -0,0 +1,234 @@
+// Gapped pattern search. A pattern is a sequence of literal token runs separated by gaps of arbitrary tokens, written
+// as query strings where "*" stands for exactly one token and "[m-n]" (or "[n]") for between m and n tokens, e.g.
+// ["the", "*", "of"] or ["in", "the", "[0-3]", "of"]. Patterns must begin and end with a literal token.
+
+use std::collections::HashMap;
+
+use corpus::corpus::{Corpus, SearchError};
+use corpus::stringmap::Stringmap;
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternElement {
+    Tokens(Vec<usize>),
+    Gap(usize, usize),
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub elements: Vec<PatternElement>,
+}
+
+
+// A match of a pattern: where it starts, how many tokens it spans, and the tokens that filled each gap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternMatch<'a> {
+    pub position: usize,
+    pub length: usize,
+    pub fillers: Vec<&'a [usize]>,
+}
+
+
+impl Pattern {
+    // Parses a pattern from query strings, encoding the literal tokens with a stringmap.
+    pub fn parse(query: &[&str], stringmap: &Stringmap) -> Result<Pattern, SearchError> {
+        let mut elements: Vec<PatternElement> = Vec::new();
+        let mut words: Vec<&str> = Vec::new();
+        let mut unknown: Vec<String> = Vec::new();
+        for item in query.iter() {
+            match parse_gap(item)? {
+                Some(gap) => {
+                    if elements.is_empty() && words.is_empty() {
+                        return Err(SearchError::InvalidPattern("A pattern cannot begin with a gap.".to_string()));
+                    }
+                    if !words.is_empty() {
+                        elements.push(literal(&words, stringmap, &mut unknown));
+                        words.clear();
+                    }
+                    elements.push(gap);
+                },
+                None => words.push(item),
+            }
+        }
+        if words.is_empty() {
+            return Err(SearchError::InvalidPattern("A pattern must end with a word.".to_string()));
+        }
+        elements.push(literal(&words, stringmap, &mut unknown));
+        if !unknown.is_empty() {
+            return Err(SearchError::UnknownWords(unknown));
+        }
+        Ok(Pattern { elements: elements })
+    }
+}
+
+
+fn literal(words: &[&str], stringmap: &Stringmap, unknown: &mut Vec<String>) -> PatternElement {
+    match stringmap.encode(words) {
+        Ok(seq) => PatternElement::Tokens(seq),
+        Err(words) => {
+            unknown.extend(words);
+            PatternElement::Tokens(Vec::new())
+        },
+    }
+}
+
+
+// Parses "*", "[n]" or "[m-n]" as a gap; any other string is a word.
+fn parse_gap(item: &str) -> Result<Option<PatternElement>, SearchError> {
+    if item == "*" {
+        return Ok(Some(PatternElement::Gap(1, 1)));
+    }
+    if !(item.starts_with('[') && item.ends_with(']') && item.len() > 2) {
+        return Ok(None);
+    }
+    let invalid = || SearchError::InvalidPattern(format!("Invalid gap {}.", item));
+    let inner = &item[1..(item.len() - 1)];
+    let mut bounds = inner.splitn(2, '-');
+    let min: usize = bounds.next().unwrap().parse().map_err(|_| invalid())?;
+    let max: usize = match bounds.next() {
+        Some(max) => max.parse().map_err(|_| invalid())?,
+        None => min,
+    };
+    if max < min {
+        return Err(invalid());
+    }
+    Ok(Some(PatternElement::Gap(min, max)))
+}
+
+
+impl Corpus {
+    // Returns every match of a pattern, ordered by position (matches starting at the same position are ordered by
+    // length). The literal run with the fewest occurrences anchors the search; the rest of the pattern is checked
+    // around each of its occurrences. Matches never span documents.
+    pub fn search_pattern(&self, pattern: &Pattern) -> Vec<PatternMatch<'_>> {
+        let mut matches: Vec<PatternMatch> = Vec::new();
+        // Choose the anchor and find its occurrences.
+        let mut anchor: Option<(usize, usize, usize)> = None;
+        for (i, element) in pattern.elements.iter().enumerate() {
+            if let PatternElement::Tokens(ref seq) = *element {
+                let (lo, hi) = match self.search_binary(seq) {
+                    Ok(range) => range,
+                    Err(_) => return matches,
+                };
+                match anchor {
+                    Some((_, a_lo, a_hi)) if a_hi - a_lo <= hi - lo => {},
+                    _ => { anchor = Some((i, lo, hi)); },
+                }
+            }
+        }
+        let (anchor_index, suffix_lo, suffix_hi) = match anchor {
+            Some(a) => a,
+            None => return matches,
+        };
+        // The anchor lies between min_offset and max_offset tokens after the start of a match.
+        let (mut min_offset, mut max_offset) = (0, 0);
+        for element in pattern.elements[..anchor_index].iter() {
+            match *element {
+                PatternElement::Tokens(ref seq) => { min_offset += seq.len(); max_offset += seq.len(); },
+                PatternElement::Gap(min, max) => { min_offset += min; max_offset += max; },
+            }
+        }
+        for suffix_pos in suffix_lo..(suffix_hi + 1) {
+            let anchor_pos = self.suffix[suffix_pos];
+            let offset = self.document_position(anchor_pos).1;
+            let doc_end = self.document_end(anchor_pos);
+            for start_offset in min_offset..(max_offset + 1) {
+                if start_offset > offset {
+                    break;
+                }
+                let start = anchor_pos - start_offset;
+                let mut found: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+                self.match_elements(pattern, 0, start, doc_end, &mut Vec::new(), &mut found, anchor_index, anchor_pos);
+                for (end, gaps) in found {
+                    let fillers = gaps.iter().map(|&(a, b)| &self.sequence[a..b]).collect();
+                    matches.push(PatternMatch { position: start, length: end - start, fillers: fillers });
+                }
+            }
+        }
+        matches.sort_by_key(|m| (m.position, m.length));
+        matches
+    }
+
+    // Returns the number of matches of a pattern for each distinct combination of gap fillers, most frequent first.
+    pub fn pattern_filler_counts(&self, pattern: &Pattern) -> Vec<(Vec<&[usize]>, usize)> {
+        let mut counts: HashMap<Vec<&[usize]>, usize> = HashMap::new();
+        for m in self.search_pattern(pattern) {
+            *counts.entry(m.fillers).or_insert(0) += 1;
+        }
+        let mut counts: Vec<(Vec<&[usize]>, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    // Matches pattern elements from index onwards starting at a sequence position, collecting the end position and
+    // gap ranges of every complete match in which the anchor element lands at anchor_pos.
+    #[allow(clippy::too_many_arguments)]
+    fn match_elements(&self, pattern: &Pattern, index: usize, pos: usize, doc_end: usize, gaps: &mut Vec<(usize, usize)>,
+                      found: &mut Vec<(usize, Vec<(usize, usize)>)>, anchor_index: usize, anchor_pos: usize) {
+        if index == pattern.elements.len() {
+            found.push((pos, gaps.clone()));
+            return;
+        }
+        if index == anchor_index && pos != anchor_pos {
+            return;
+        }
+        match pattern.elements[index] {
+            PatternElement::Tokens(ref seq) => {
+                let end = pos + seq.len();
+                if end <= doc_end && self.sequence[pos..end] == seq[..] {
+                    self.match_elements(pattern, index + 1, end, doc_end, gaps, found, anchor_index, anchor_pos);
+                }
+            },
+            PatternElement::Gap(min, max) => {
+                for len in min..(max + 1) {
+                    if pos + len > doc_end {
+                        break;
+                    }
+                    gaps.push((pos, pos + len));
+                    self.match_elements(pattern, index + 1, pos + len, doc_end, gaps, found, anchor_index, anchor_pos);
+                    gaps.pop();
+                }
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus_from(text: &str) -> Corpus {
+        Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn parse_patterns() {
+        let c = corpus_from("in the middle of the night");
+        let p = Pattern::parse(&["in", "the", "[0-3]", "of"], &c.stringmap).unwrap();
+        assert_eq!(p.elements.len(), 3);
+        assert_eq!(p.elements[1], PatternElement::Gap(0, 3));
+        assert_eq!(Pattern::parse(&["the", "*", "day"], &c.stringmap), Err(SearchError::UnknownWords(vec!["day".to_string()])));
+        assert!(Pattern::parse(&["*", "of"], &c.stringmap).is_err());
+        assert!(Pattern::parse(&["of", "[3-1]", "the"], &c.stringmap).is_err());
+    }
+
+    #[test]
+    fn search_gapped_patterns() {
+        let c = corpus_from("the end of the day and the start of the week and the end of it");
+        let p = Pattern::parse(&["the", "*", "of"], &c.stringmap).unwrap();
+        let matches = c.search_pattern(&p);
+        let positions: Vec<usize> = matches.iter().map(|m| m.position).collect();
+        assert_eq!(positions, vec![0, 6, 12]);
+        let counts = c.pattern_filler_counts(&p);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(c.decode(counts[0].0[0]), vec!["end"]);
+        assert_eq!(counts[0].1, 2);
+        let p = Pattern::parse(&["and", "[0-3]", "of"], &c.stringmap).unwrap();
+        let matches = c.search_pattern(&p);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].length, 4);
+        assert_eq!(c.decode(matches[1].fillers[0]), vec!["the", "end"]);
+    }
+}
@@ -0,0 +1,537 @@
+// Saving a built corpus to disk and loading it back.
+//
+// On-disk format (version 2). All integers are unsigned 64-bit little-endian unless noted.
+//
+//     offset  size  field
+//     0       8     magic bytes "CORPUSLB"
+//     8       4     format version (u32)
+//     12      4     reserved, zero (u32)
+//     16      8     number of strings in the stringmap
+//     24      8     number of tokens in the sequence
+//     32      8     length in bytes of the strings block (a multiple of 8)
+//     40      8     number of documents
+//     48      8     FNV-1a checksum of every byte after the header
+//     56            strings block: for each code in order, its length followed by its UTF-8 bytes, zero-padded to
+//                   a multiple of 8 bytes
+//                   document start positions (one integer per document)
+//                   sequence array (one integer per token)
+//                   suffix array (one integer per token)
+//                   LCP array (one integer per token)
+//
+// The header and strings block are multiples of 8 bytes so the arrays are aligned, which lets MappedCorpus use them
+// in place on 64-bit little-endian machines.
+//
+// MappedCorpus::open validates the header, the strings block and the document starts. Corpus::load,
+// MappedCorpus::verify and MappedCorpus::open_verified also check the checksum and that the arrays are consistent:
+// every code is in the stringmap, the suffix array is a permutation of the sequence positions sorted by suffix (cut
+// off at document ends), and each LCP entry is the common prefix length of the suffixes it sits between.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::slice;
+
+use memmap2::Mmap;
+
+use corpus::corpus::{Corpus, SearchError};
+use corpus::documents::document_end;
+use corpus::sequence::sequence_compare_n;
+use corpus::stringmap::Stringmap;
+
+
+const MAGIC: &[u8; 8] = b"CORPUSLB";
+const FORMAT_VERSION: u32 = 2;
+const HEADER_SIZE: usize = 56;
+
+
+struct Header {
+    num_strings: usize,
+    num_tokens: usize,
+    strings_bytes: usize,
+    num_documents: usize,
+    checksum: u64,
+}
+
+
+impl Header {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(self.num_strings as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.num_tokens as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.strings_bytes as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.num_documents as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    // Parses and validates a header, given the total length of the file it came from.
+    fn from_bytes(bytes: &[u8], file_len: u64) -> Result<Header, Error> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..8] != MAGIC {
+            return Err(invalid("Not a corpus file."));
+        }
+        let version = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!("Unsupported corpus format version {} (expected {}).", version, FORMAT_VERSION)));
+        }
+        let header = Header {
+            num_strings: read_u64(&bytes[16..24]) as usize,
+            num_tokens: read_u64(&bytes[24..32]) as usize,
+            strings_bytes: read_u64(&bytes[32..40]) as usize,
+            num_documents: read_u64(&bytes[40..48]) as usize,
+            checksum: read_u64(&bytes[48..56]),
+        };
+        let expected_len = (header.num_tokens as u64).checked_mul(3)
+            .and_then(|words| words.checked_add(header.num_documents as u64))
+            .and_then(|words| words.checked_mul(8))
+            .and_then(|arrays| arrays.checked_add(HEADER_SIZE as u64))
+            .and_then(|total| total.checked_add(header.strings_bytes as u64));
+        if header.strings_bytes % 8 != 0 || header.num_documents == 0 || expected_len != Some(file_len) {
+            return Err(invalid("Corpus file has inconsistent section sizes."));
+        }
+        Ok(header)
+    }
+}
+
+
+// 64-bit FNV-1a hash, used to detect corrupted or truncated files.
+struct Checksum {
+    hash: u64,
+}
+
+
+impl Checksum {
+    fn new() -> Checksum {
+        Checksum { hash: 0xcbf29ce484222325 }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for b in bytes.iter() {
+            self.hash ^= *b as u64;
+            self.hash = self.hash.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+
+impl Corpus {
+    // Writes the corpus to a file in the versioned binary format described above.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let strings_block = encode_strings(&self.stringmap);
+        // Checksum the body before writing so the header can be written first.
+        let mut checksum = Checksum::new();
+        checksum.update(&strings_block);
+        for array in [&self.documents, &self.sequence, &self.suffix, &self.lcp].iter() {
+            for v in array.iter() {
+                checksum.update(&(*v as u64).to_le_bytes());
+            }
+        }
+        let header = Header {
+            num_strings: self.stringmap.strings.len(),
+            num_tokens: self.sequence.len(),
+            strings_bytes: strings_block.len(),
+            num_documents: self.documents.len(),
+            checksum: checksum.hash,
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&header.to_bytes())?;
+        writer.write_all(&strings_block)?;
+        for array in [&self.documents, &self.sequence, &self.suffix, &self.lcp].iter() {
+            for v in array.iter() {
+                writer.write_all(&(*v as u64).to_le_bytes())?;
+            }
+        }
+        writer.flush()
+    }
+
+    // Reads a corpus written by save, validating its version, section sizes, checksum and contents.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Corpus, Error> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut header_bytes = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header_bytes)?;
+        let header = Header::from_bytes(&header_bytes, file_len)?;
+        let mut checksum = Checksum::new();
+        // Read the strings block.
+        let mut strings_block = vec![0u8; header.strings_bytes];
+        reader.read_exact(&mut strings_block)?;
+        checksum.update(&strings_block);
+        let stringmap = decode_strings(&strings_block, header.num_strings)?;
+        // Read the document starts and then the arrays.
+        let mut arrays: Vec<Vec<usize>> = Vec::with_capacity(4);
+        let mut word = [0u8; 8];
+        for &len in [header.num_documents, header.num_tokens, header.num_tokens, header.num_tokens].iter() {
+            let mut array: Vec<usize> = Vec::with_capacity(len);
+            for _ in 0..len {
+                reader.read_exact(&mut word)?;
+                checksum.update(&word);
+                array.push(u64::from_le_bytes(word) as usize);
+            }
+            arrays.push(array);
+        }
+        if checksum.hash != header.checksum {
+            return Err(invalid("Corpus file checksum does not match its contents."));
+        }
+        let lcp = arrays.pop().unwrap();
+        let suffix = arrays.pop().unwrap();
+        let sequence = arrays.pop().unwrap();
+        let documents = arrays.pop().unwrap();
+        validate_arrays(&documents, &sequence, &suffix, &lcp, header.num_strings)?;
+        Ok(Corpus { sequence: sequence, suffix: suffix, lcp: lcp, documents: documents, stringmap: stringmap })
+    }
+}
+
+
+// A corpus file opened by memory-mapping, so that its arrays are paged in on demand rather than read up front. Only
+// the stringmap and document starts are decoded when the file is opened. Requires a 64-bit little-endian machine.
+pub struct MappedCorpus {
+    mmap: Mmap,
+    pub documents: Vec<usize>,
+    pub stringmap: Stringmap,
+    num_tokens: usize,
+    arrays_offset: usize,
+}
+
+
+impl MappedCorpus {
+    // Maps a corpus file written by Corpus::save. The header and document starts are validated, but neither the
+    // checksum nor the contents of the mapped arrays are checked, so searching a corrupted file can panic. Use
+    // open_verified (or call verify) to check the whole file first.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedCorpus, Error> {
+        if cfg!(target_endian = "big") || mem::size_of::<usize>() != 8 {
+            return Err(Error::new(ErrorKind::Unsupported, "Memory-mapped corpora require a 64-bit little-endian machine."));
+        }
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mmap = unsafe { Mmap::map(&file)? };
+        let header = Header::from_bytes(&mmap, file_len)?;
+        let sizes = || invalid("Corpus file has inconsistent section sizes.");
+        let documents_offset = HEADER_SIZE.checked_add(header.strings_bytes).ok_or_else(sizes)?;
+        let arrays_offset = header.num_documents.checked_mul(8).and_then(|bytes| bytes.checked_add(documents_offset))
+            .ok_or_else(sizes)?;
+        let stringmap = decode_strings(&mmap[HEADER_SIZE..documents_offset], header.num_strings)?;
+        let documents: Vec<usize> = mmap[documents_offset..arrays_offset].chunks(8).map(|w| read_u64(w) as usize).collect();
+        validate_documents(&documents, header.num_tokens)?;
+        Ok(MappedCorpus {
+            mmap: mmap,
+            documents: documents,
+            stringmap: stringmap,
+            num_tokens: header.num_tokens,
+            arrays_offset: arrays_offset,
+        })
+    }
+
+    // Maps a corpus file as open does and then verifies it, so that it is as safe to search as a loaded corpus.
+    pub fn open_verified<P: AsRef<Path>>(path: P) -> Result<MappedCorpus, Error> {
+        let mapped = MappedCorpus::open(path)?;
+        mapped.verify()?;
+        Ok(mapped)
+    }
+
+    // Checks the file checksum and the contents of the mapped arrays, reading the whole file.
+    pub fn verify(&self) -> Result<(), Error> {
+        let header = Header::from_bytes(&self.mmap, self.mmap.len() as u64)?;
+        let mut checksum = Checksum::new();
+        checksum.update(&self.mmap[HEADER_SIZE..]);
+        if checksum.hash != header.checksum {
+            return Err(invalid("Corpus file checksum does not match its contents."));
+        }
+        validate_arrays(&self.documents, self.sequence(), self.suffix(), self.lcp(), header.num_strings)
+    }
+
+    pub fn sequence(&self) -> &[usize] {
+        self.array(0)
+    }
+
+    pub fn suffix(&self) -> &[usize] {
+        self.array(1)
+    }
+
+    pub fn lcp(&self) -> &[usize] {
+        self.array(2)
+    }
+
+    // Returns range of suffix array that points to required sequence in corpus, as Corpus::search_binary does.
+    pub fn search_binary(&self, seq: &[usize]) -> Result<(usize, usize), bool> {
+        let (sequence, suffix) = (self.sequence(), self.suffix());
+        let n = seq.len();
+        // Find the first suffix not less than the sequence, then the first suffix greater than it.
+        let bound = |stop_at_equal: bool| {
+            let (mut lo, mut hi) = (0, suffix.len());
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let seq_pos = suffix[mid];
+                let end = document_end(&self.documents, sequence.len(), seq_pos);
+                let cmp = sequence_compare_n(&sequence[seq_pos..end], seq, &n);
+                if cmp == ::std::cmp::Ordering::Less || (!stop_at_equal && cmp == ::std::cmp::Ordering::Equal) {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            lo
+        };
+        let (suffix_lo, suffix_end) = (bound(true), bound(false));
+        if suffix_lo == suffix_end {
+            Err(false)
+        } else {
+            Ok((suffix_lo, suffix_end - 1))
+        }
+    }
+
+    // Returns range of suffix array that points to the required sequence of words, as Corpus::search_strings does.
+    pub fn search_strings(&self, words: &[&str]) -> Result<(usize, usize), SearchError> {
+        match self.stringmap.encode(words) {
+            Ok(seq) => self.search_binary(&seq).map_err(|_| SearchError::NotFound),
+            Err(unknown) => Err(SearchError::UnknownWords(unknown)),
+        }
+    }
+
+    // Copies the mapped arrays into an in-memory corpus.
+    pub fn to_corpus(&self) -> Corpus {
+        Corpus {
+            sequence: self.sequence().to_vec(),
+            suffix: self.suffix().to_vec(),
+            lcp: self.lcp().to_vec(),
+            documents: self.documents.clone(),
+            stringmap: self.stringmap.clone(),
+        }
+    }
+
+    fn array(&self, index: usize) -> &[usize] {
+        let start = self.arrays_offset + index * self.num_tokens * 8;
+        let bytes = &self.mmap[start..(start + self.num_tokens * 8)];
+        // The mapping is page-aligned and every section starts at a multiple of 8 bytes, so the cast is aligned.
+        unsafe { slice::from_raw_parts(bytes.as_ptr() as *const usize, self.num_tokens) }
+    }
+}
+
+
+fn encode_strings(stringmap: &Stringmap) -> Vec<u8> {
+    let mut block: Vec<u8> = Vec::new();
+    for s in stringmap.strings.iter() {
+        block.extend_from_slice(&(s.len() as u64).to_le_bytes());
+        block.extend_from_slice(s.as_bytes());
+    }
+    while block.len() % 8 != 0 {
+        block.push(0);
+    }
+    block
+}
+
+
+fn decode_strings(block: &[u8], num_strings: usize) -> Result<Stringmap, Error> {
+    let mut stringmap = Stringmap::new();
+    let mut pos = 0;
+    for _ in 0..num_strings {
+        if pos + 8 > block.len() {
+            return Err(invalid("Corpus file strings block is truncated."));
+        }
+        let len = read_u64(&block[pos..(pos + 8)]) as usize;
+        pos += 8;
+        if len > block.len() - pos {
+            return Err(invalid("Corpus file strings block is truncated."));
+        }
+        let s = match String::from_utf8(block[pos..(pos + len)].to_vec()) {
+            Ok(s) => s,
+            Err(_) => return Err(invalid("Corpus file contains a string that is not valid UTF-8.")),
+        };
+        pos += len;
+        if stringmap.contains(&s) {
+            return Err(invalid("Corpus file contains a duplicate string."));
+        }
+        stringmap.add(&s);
+    }
+    Ok(stringmap)
+}
+
+
+// Document starts must begin at 0 and never decrease (empty documents share a start with the next document) or pass
+// the end of the sequence.
+fn validate_documents(documents: &[usize], num_tokens: usize) -> Result<(), Error> {
+    if documents.is_empty() || documents[0] != 0 || documents.windows(2).any(|w| w[0] > w[1]) ||
+        documents[documents.len() - 1] > num_tokens {
+        return Err(invalid("Corpus file document starts are out of order."));
+    }
+    Ok(())
+}
+
+
+fn validate_arrays(documents: &[usize], sequence: &[usize], suffix: &[usize], lcp: &[usize], num_strings: usize) -> Result<(), Error> {
+    let n = sequence.len();
+    validate_documents(documents, n)?;
+    for &code in sequence.iter() {
+        if code >= num_strings {
+            return Err(invalid("Corpus file sequence contains an unknown code."));
+        }
+    }
+    // Each position must appear in the suffix array exactly once.
+    let mut seen: Vec<u64> = vec![0; n.div_ceil(64)];
+    for &p in suffix.iter() {
+        if p >= n || seen[p / 64] & (1 << (p % 64)) != 0 {
+            return Err(invalid("Corpus file suffix array is not a permutation of the sequence positions."));
+        }
+        seen[p / 64] |= 1 << (p % 64);
+    }
+    // Adjacent suffixes must be in order, which takes time proportional to the sum of their common prefix lengths.
+    let document_suffix = |p: usize| &sequence[p..document_end(documents, n, p)];
+    for i in 0..n {
+        let mut common = 0;
+        if i > 0 {
+            let (prev, next) = (document_suffix(suffix[i - 1]), document_suffix(suffix[i]));
+            common = prev.iter().zip(next.iter()).take_while(|&(a, b)| a == b).count();
+            if common < prev.len() && (common == next.len() || prev[common] > next[common]) {
+                return Err(invalid("Corpus file suffix array is out of order."));
+            }
+        }
+        if lcp[i] != common {
+            return Err(invalid("Corpus file LCP array does not match the suffix array."));
+        }
+    }
+    Ok(())
+}
+
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&bytes[0..8]);
+    u64::from_le_bytes(word)
+}
+
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("corpuslib-{}-{}.bin", name, process::id()))
+    }
+
+    fn corpus_from(text: &str) -> Corpus {
+        Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let c = corpus_from("the cat sat on the mat and the cat slept");
+        let path = temp_path("round-trip");
+        c.save(&path).unwrap();
+        let loaded = Corpus::load(&path).unwrap();
+        assert_eq!(loaded.sequence, c.sequence);
+        assert_eq!(loaded.suffix, c.suffix);
+        assert_eq!(loaded.lcp, c.lcp);
+        assert_eq!(loaded.stringmap.strings, c.stringmap.strings);
+        assert_eq!(loaded.search_strings(&["the", "cat"]), c.search_strings(&["the", "cat"]));
+        let mapped = MappedCorpus::open(&path).unwrap();
+        mapped.verify().unwrap();
+        assert_eq!(mapped.suffix(), &c.suffix[..]);
+        for seq_pos in 0..c.sequence.len() {
+            let seq = &c.sequence[seq_pos..(seq_pos + 1)];
+            assert_eq!(mapped.search_binary(seq), c.search_binary(seq));
+        }
+        assert_eq!(mapped.search_strings(&["the", "dog"]), Err(SearchError::UnknownWords(vec!["dog".to_string()])));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn documents_round_trip() {
+        let documents = vec![vec!["a".to_string(), "b".to_string()], vec![], vec!["b".to_string(), "c".to_string()]];
+        let c = Corpus::from_documents(documents);
+        let path = temp_path("documents");
+        c.save(&path).unwrap();
+        assert_eq!(Corpus::load(&path).unwrap().documents, c.documents);
+        let mapped = MappedCorpus::open(&path).unwrap();
+        assert_eq!(mapped.documents, c.documents);
+        assert_eq!(mapped.search_strings(&["b", "b"]), Err(SearchError::NotFound));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_corruption() {
+        let c = corpus_from("a b a b c");
+        let path = temp_path("corrupt");
+        c.save(&path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(Corpus::load(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+        assert!(MappedCorpus::open(&path).unwrap().verify().is_err());
+        bytes[8] = 99;
+        fs::write(&path, &bytes).unwrap();
+        assert!(Corpus::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_rejects_bad_arrays() {
+        let c = Corpus::from_documents(vec![vec!["a".to_string(), "b".to_string()], vec!["a".to_string()]]);
+        let path = temp_path("bad-arrays");
+        c.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let strings_bytes = read_u64(&bytes[32..40]) as usize;
+        let documents_offset = HEADER_SIZE + strings_bytes;
+        let arrays_offset = documents_offset + 2 * 8;
+        // A document start past the end of the sequence is caught by open.
+        let mut corrupt = bytes.clone();
+        corrupt[(documents_offset + 8)..(documents_offset + 16)].copy_from_slice(&9u64.to_le_bytes());
+        fs::write(&path, &corrupt).unwrap();
+        assert_eq!(MappedCorpus::open(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+        // An out-of-range suffix entry is only caught by verification.
+        let mut corrupt = bytes.clone();
+        let suffix_offset = arrays_offset + 3 * 8;
+        corrupt[suffix_offset..(suffix_offset + 8)].copy_from_slice(&7u64.to_le_bytes());
+        fs::write(&path, &corrupt).unwrap();
+        assert!(MappedCorpus::open(&path).is_ok());
+        assert!(MappedCorpus::open_verified(&path).is_err());
+        fs::write(&path, &bytes).unwrap();
+        assert!(MappedCorpus::open_verified(&path).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_unsorted_suffix_array() {
+        let c = corpus_from("a b a b c");
+        let path = temp_path("unsorted");
+        c.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let suffix_offset = HEADER_SIZE + read_u64(&bytes[32..40]) as usize + 8 + 5 * 8;
+        // Rewrites two suffix entries and the checksum, so only the array checks can catch the change.
+        let corrupt = |first: usize, second: usize| {
+            let mut corrupt = bytes.clone();
+            corrupt[suffix_offset..(suffix_offset + 8)].copy_from_slice(&(first as u64).to_le_bytes());
+            corrupt[(suffix_offset + 8)..(suffix_offset + 16)].copy_from_slice(&(second as u64).to_le_bytes());
+            let mut checksum = Checksum::new();
+            checksum.update(&corrupt[HEADER_SIZE..]);
+            corrupt[48..56].copy_from_slice(&checksum.hash.to_le_bytes());
+            fs::write(&path, &corrupt).unwrap();
+        };
+        // Swapped entries are still a permutation but are out of order.
+        corrupt(c.suffix[1], c.suffix[0]);
+        assert_eq!(Corpus::load(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+        assert!(MappedCorpus::open(&path).unwrap().verify().is_err());
+        assert!(MappedCorpus::open_verified(&path).is_err());
+        // A repeated entry is not a permutation.
+        corrupt(c.suffix[0], c.suffix[0]);
+        assert!(Corpus::load(&path).is_err());
+        assert!(MappedCorpus::open_verified(&path).is_err());
+        corrupt(c.suffix[0], c.suffix[1]);
+        assert!(Corpus::load(&path).is_ok());
+        assert!(MappedCorpus::open_verified(&path).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+}
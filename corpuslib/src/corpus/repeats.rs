@@ -0,0 +1,164 @@
+// Repeat analysis over the suffix and LCP arrays of a corpus.
+
+use corpus::corpus::Corpus;
+
+
+// A repeated token sequence, identified by the range of the suffix array whose suffixes all begin with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repeat {
+    pub length: usize,
+    pub suffix_lo: usize,
+    pub suffix_hi: usize,
+}
+
+
+impl Repeat {
+    // Returns the number of times the repeat occurs in the corpus.
+    pub fn frequency(&self) -> usize {
+        self.suffix_hi - self.suffix_lo + 1
+    }
+
+    // Returns the repeated token sequence.
+    pub fn sequence<'a, T>(&self, corpus: &'a Corpus<T>) -> &'a [usize] {
+        let seq_pos = corpus.suffix[self.suffix_lo];
+        &corpus.sequence[seq_pos..(seq_pos + self.length)]
+    }
+}
+
+
+// Summary of the tokens immediately to the left of a set of suffixes, used to test for left-maximality.
+#[derive(Clone, Copy, PartialEq)]
+enum LeftContext {
+    Empty,
+    Single(usize),
+    Diverse,
+}
+
+
+impl LeftContext {
+    fn merge(self, other: LeftContext) -> LeftContext {
+        match (self, other) {
+            (LeftContext::Empty, c) | (c, LeftContext::Empty) => c,
+            (LeftContext::Single(a), LeftContext::Single(b)) if a == b => LeftContext::Single(a),
+            _ => LeftContext::Diverse,
+        }
+    }
+}
+
+
+impl<T> Corpus<T> {
+    // Returns the longest token sequence that occurs at least twice in the corpus (None if nothing repeats).
+    pub fn longest_repeat(&self) -> Option<&[usize]> {
+        self.longest_repeat_position().map(|(seq_pos, length)| &self.sequence[seq_pos..(seq_pos + length)])
+    }
+
+    // Returns the longest repeat as the sequence position of one of its occurrences and its length.
+    pub fn longest_repeat_position(&self) -> Option<(usize, usize)> {
+        let mut best: Option<usize> = None;
+        for i in 1..self.lcp.len() {
+            match best {
+                Some(b) if self.lcp[b] >= self.lcp[i] => {},
+                _ => { best = Some(i); },
+            }
+        }
+        match best {
+            Some(b) if self.lcp[b] > 0 => Some((self.suffix[b], self.lcp[b])),
+            _ => None,
+        }
+    }
+
+    // Returns every maximal repeat (one that cannot be extended to the left or right without losing an occurrence)
+    // that is at least min_length tokens long and occurs at least min_freq times. Repeats are found by a bottom-up
+    // traversal of the LCP intervals, so the whole search is linear in the size of the corpus.
+    pub fn maximal_repeats(&self, min_length: usize, min_freq: usize) -> Vec<Repeat> {
+        let mut repeats: Vec<Repeat> = Vec::new();
+        let n = self.suffix.len();
+        if n == 0 {
+            return repeats;
+        }
+        // Stack of open LCP intervals: (common prefix length, left suffix bound, left context so far).
+        let mut stack: Vec<(usize, usize, LeftContext)> = vec![(0, 0, LeftContext::Empty)];
+        for i in 1..(n + 1) {
+            let l = if i < n { self.lcp[i] } else { 0 };
+            let mut lb = i - 1;
+            let mut carried = self.left_context(i - 1);
+            // Close every interval whose common prefix is longer than the one shared with the next suffix.
+            while l < stack[stack.len() - 1].0 {
+                let (h, interval_lb, context) = stack.pop().unwrap();
+                let context = context.merge(carried);
+                let repeat = Repeat { length: h, suffix_lo: interval_lb, suffix_hi: i - 1 };
+                if h >= min_length && repeat.frequency() >= min_freq && context == LeftContext::Diverse {
+                    repeats.push(repeat);
+                }
+                carried = context;
+                lb = interval_lb;
+            }
+            let top = stack.len() - 1;
+            if l > stack[top].0 {
+                stack.push((l, lb, carried));
+            } else {
+                stack[top].2 = stack[top].2.merge(carried);
+            }
+        }
+        repeats
+    }
+
+    // Returns the number of distinct non-empty token sequences in the corpus (not counting those spanning documents).
+    pub fn distinct_substrings(&self) -> usize {
+        let mut total = 0;
+        for doc in 0..self.num_documents() {
+            let n = self.document(doc).len();
+            total += n * (n + 1) / 2;
+        }
+        let shared: usize = self.lcp.iter().sum();
+        total - shared
+    }
+
+    fn left_context(&self, suffix_pos: usize) -> LeftContext {
+        let seq_pos = self.suffix[suffix_pos];
+        if self.document_position(seq_pos).1 == 0 {
+            // Nothing precedes the start of a document, so an occurrence there is always left-maximal.
+            LeftContext::Diverse
+        } else {
+            LeftContext::Single(self.sequence[seq_pos - 1])
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use corpus::corpus::Corpus;
+
+    fn corpus_from(text: &str) -> Corpus {
+        Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn longest_repeat() {
+        let c = corpus_from("a b c d x a b c d y b c");
+        let expected: Vec<usize> = ["a", "b", "c", "d"].iter().map(|w| *c.stringmap.get(*w).unwrap()).collect();
+        assert_eq!(c.longest_repeat(), Some(&expected[..]));
+        assert_eq!(corpus_from("a b c").longest_repeat(), None);
+    }
+
+    #[test]
+    fn maximal_repeats() {
+        let c = corpus_from("a b c d x a b c d y b c");
+        let repeats = c.maximal_repeats(1, 2);
+        let mut found: Vec<(usize, usize)> = repeats.iter().map(|r| (r.length, r.frequency())).collect();
+        found.sort();
+        // "a b c d" occurs twice and "b c" three times; "b c d", "c d" etc. are not left-maximal.
+        assert_eq!(found, vec![(2, 3), (4, 2)]);
+        assert_eq!(c.maximal_repeats(3, 2).len(), 1);
+        assert_eq!(c.maximal_repeats(1, 3).len(), 1);
+    }
+
+    #[test]
+    fn distinct_substrings() {
+        assert_eq!(corpus_from("a a a").distinct_substrings(), 3);
+        assert_eq!(corpus_from("a b a b").distinct_substrings(), 7);
+        let documents = vec![vec!["a".to_string(), "b".to_string()], vec!["a".to_string(), "b".to_string()]];
+        assert_eq!(Corpus::from_documents(documents).distinct_substrings(), 3);
+    }
+}
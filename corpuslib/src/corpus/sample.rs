@@ -0,0 +1,106 @@
+// Uniform random sampling of the occurrences of a sequence, reproducible from a seed.
+
+use std::collections::HashSet;
+
+use rand::{Rng, SeedableRng};
+use rand::isaac::Isaac64Rng;
+
+use corpus::corpus::{Corpus, SearchError};
+
+
+impl<T> Corpus<T> {
+    // Returns up to n sequence positions chosen uniformly at random, without replacement, from the occurrences of a
+    // sequence. The same seed always gives the same sample. Positions are returned in corpus order; if the sequence
+    // occurs n times or fewer, every occurrence is returned.
+    pub fn sample_positions(&self, seq: &[usize], n: usize, seed: usize) -> Vec<usize> {
+        let (suffix_lo, suffix_hi) = match self.search_binary(seq) {
+            Ok(range) => range,
+            Err(_) => return Vec::new(),
+        };
+        let mut positions: Vec<usize> = sample_indices(suffix_hi - suffix_lo + 1, n, seed).iter()
+            .map(|i| self.suffix[suffix_lo + i])
+            .collect();
+        positions.sort();
+        positions
+    }
+}
+
+
+impl Corpus {
+    // Returns a sample of the positions of a sequence of words, as sample_positions does.
+    pub fn sample_strings(&self, words: &[&str], n: usize, seed: usize) -> Result<Vec<usize>, SearchError> {
+        match self.stringmap.encode(words) {
+            Ok(seq) => {
+                let positions = self.sample_positions(&seq, n, seed);
+                if positions.is_empty() { Err(SearchError::NotFound) } else { Ok(positions) }
+            },
+            Err(unknown) => Err(SearchError::UnknownWords(unknown)),
+        }
+    }
+}
+
+
+// Chooses min(n, k) distinct indices from 0..k uniformly at random using Floyd's algorithm, which takes O(n) time and
+// space however large k is. The generator is ISAAC-64 rather than StdRng, whose algorithm depends on the platform, so
+// a seed gives the same sample everywhere (rand is pinned to 0.3 in Cargo.toml to keep it so across releases).
+pub fn sample_indices(k: usize, n: usize, seed: usize) -> Vec<usize> {
+    if n >= k {
+        return (0..k).collect();
+    }
+    let seed: &[u64] = &[seed as u64];
+    let mut rng: Isaac64Rng = SeedableRng::from_seed(seed);
+    let mut chosen: HashSet<usize> = HashSet::with_capacity(n);
+    let mut indices: Vec<usize> = Vec::with_capacity(n);
+    for j in (k - n)..k {
+        let t = rng.gen_range(0, j + 1);
+        let index = if chosen.contains(&t) { j } else { t };
+        chosen.insert(index);
+        indices.push(index);
+    }
+    indices
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_reproducible_and_distinct() {
+        let text = "of the a of the b of the c of the d of the e of the f of the";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        let sample = c.sample_strings(&["of", "the"], 3, 42).unwrap();
+        assert_eq!(sample.len(), 3);
+        assert_eq!(sample, c.sample_strings(&["of", "the"], 3, 42).unwrap());
+        let mut distinct = sample.clone();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 3);
+        for &p in sample.iter() {
+            assert_eq!(c.decode(&c.sequence[p..(p + 2)]), vec!["of", "the"]);
+        }
+        assert_eq!(c.sample_strings(&["of", "the"], 100, 1).unwrap().len(), 7);
+        assert_eq!(c.sample_strings(&["the", "the"], 1, 1), Err(SearchError::NotFound));
+    }
+
+    #[test]
+    fn samples_are_fixed_for_a_seed() {
+        // Pins the output for known seeds, so a change of generator or algorithm is caught.
+        assert_eq!(sample_indices(1000, 5, 7), vec![541, 897, 761, 887, 740]);
+        assert_eq!(sample_indices(20, 3, 0), vec![9, 18, 11]);
+        let text = "of the a of the b of the c of the d of the e of the f of the";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        assert_eq!(c.sample_strings(&["of", "the"], 3, 42), Ok(vec![3, 6, 15]));
+    }
+
+    #[test]
+    fn sample_indices_cover_range() {
+        // Every index should be chosen at some point across many seeds.
+        let mut seen = [false; 20];
+        for seed in 0..200 {
+            for i in sample_indices(20, 3, seed) {
+                seen[i] = true;
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
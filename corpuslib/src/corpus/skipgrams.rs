@@ -0,0 +1,184 @@
+// k-skip-n-grams: sequences of n tokens, in order, taken from a span in which at most k tokens are skipped
+// (Guthrie et al., 2006). Contiguous ngrams are the 0-skip-n-grams. Skip-grams never span documents.
+
+use std::collections::HashMap;
+
+use corpus::corpus::Corpus;
+use corpus::stringmap::Stringmap;
+
+
+// Counts k-skip-n-grams over a stream of tokens. Each skip-gram is counted once, when its last token arrives, so only
+// the last n + k tokens need to be kept.
+pub struct SkipgramCounter {
+    n: usize,
+    k: usize,
+    window: Vec<usize>,
+    counts: HashMap<Vec<usize>, usize>,
+    pub stringmap: Stringmap,
+}
+
+
+impl SkipgramCounter {
+    pub fn new(n: usize, k: usize) -> SkipgramCounter {
+        SkipgramCounter::with_stringmap(n, k, Stringmap::new())
+    }
+
+    // Creates a counter that codes tokens with an existing stringmap, adding any new tokens to it.
+    pub fn with_stringmap(n: usize, k: usize, stringmap: Stringmap) -> SkipgramCounter {
+        SkipgramCounter { n: n, k: k, window: Vec::with_capacity(n + k), counts: HashMap::new(), stringmap: stringmap }
+    }
+
+    pub fn register(&mut self, token: &str) {
+        let code = match self.stringmap.get(token) {
+            Some(&code_value) => code_value,
+            None => self.stringmap.add(token),
+        };
+        self.register_code(code);
+    }
+
+    // Registers a token that has already been coded.
+    pub fn register_code(&mut self, code: usize) {
+        if self.n == 0 {
+            return;
+        }
+        if self.window.len() == self.n + self.k {
+            self.window.remove(0);
+        }
+        self.window.push(code);
+        let counts = &mut self.counts;
+        skipgrams_ending(&self.window, self.n, |skipgram| {
+            *counts.entry(skipgram.to_vec()).or_insert(0) += 1;
+        });
+    }
+
+    // Ends the current document so that no skip-gram spans it and the next token.
+    pub fn end_document(&mut self) {
+        self.window.clear();
+    }
+
+    pub fn counts(&self) -> &HashMap<Vec<usize>, usize> {
+        &self.counts
+    }
+
+    // Returns the skip-grams occurring at least min_count times, most frequent first (ties in code order).
+    pub fn sorted_counts(&self, min_count: usize) -> Vec<(Vec<usize>, usize)> {
+        let mut counts: Vec<(Vec<usize>, usize)> = self.counts.iter()
+            .filter(|&(_, &count)| count >= min_count)
+            .map(|(skipgram, &count)| (skipgram.clone(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    // Returns sorted_counts decoded to strings.
+    pub fn sorted_strings(&self, min_count: usize) -> Vec<(Vec<&str>, usize)> {
+        self.sorted_counts(min_count).into_iter()
+            .map(|(skipgram, count)| (self.stringmap.decode_sequence(&skipgram).unwrap(), count))
+            .collect()
+    }
+}
+
+
+impl<T> Corpus<T> {
+    // Returns each distinct k-skip-n-gram occurring at least min_count times with its frequency, most frequent first
+    // (ties in code order).
+    pub fn skipgram_counts(&self, n: usize, k: usize, min_count: usize) -> Vec<(Vec<usize>, usize)> {
+        let mut counter = SkipgramCounter::with_stringmap(n, k, Stringmap::new());
+        for doc in 0..self.num_documents() {
+            for &code in self.document(doc).iter() {
+                counter.register_code(code);
+            }
+            counter.end_document();
+        }
+        counter.sorted_counts(min_count)
+    }
+}
+
+
+impl Corpus {
+    // Returns skipgram_counts decoded to strings.
+    pub fn skipgram_strings(&self, n: usize, k: usize, min_count: usize) -> Vec<(Vec<&str>, usize)> {
+        self.skipgram_counts(n, k, min_count).into_iter()
+            .map(|(skipgram, count)| (self.decode(&skipgram), count))
+            .collect()
+    }
+}
+
+
+// Calls emit with every n-token skip-gram that ends at the last token of the window, taking the other n - 1 tokens
+// in order from anywhere earlier in the window.
+fn skipgrams_ending<F: FnMut(&[usize])>(window: &[usize], n: usize, mut emit: F) {
+    let last = window.len() - 1;
+    if last + 1 < n {
+        return;
+    }
+    let mut skipgram: Vec<usize> = Vec::with_capacity(n);
+    choose(window, last, 0, n - 1, &mut skipgram, &mut emit);
+}
+
+
+// Extends a partial skip-gram with `remaining` more tokens chosen in order from window[from..last], then the token
+// at window[last].
+fn choose<F: FnMut(&[usize])>(window: &[usize], last: usize, from: usize, remaining: usize,
+                              skipgram: &mut Vec<usize>, emit: &mut F) {
+    if remaining == 0 {
+        skipgram.push(window[last]);
+        emit(skipgram);
+        skipgram.pop();
+        return;
+    }
+    for i in from..(last + 1 - remaining) {
+        skipgram.push(window[i]);
+        choose(window, last, i + 1, remaining - 1, skipgram, emit);
+        skipgram.pop();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn check_skipgrams() {
+        // The 2-skip-bigrams of "a b c d" within one document.
+        let c = Corpus::new(tokens("a b c d"));
+        let mut bigrams: Vec<Vec<&str>> = c.skipgram_strings(2, 2, 1).into_iter().map(|(s, _)| s).collect();
+        bigrams.sort();
+        assert_eq!(bigrams, vec![vec!["a", "b"], vec!["a", "c"], vec!["a", "d"], vec!["b", "c"], vec!["b", "d"],
+                                 vec!["c", "d"]]);
+        // 0-skip-n-grams are the contiguous ngrams.
+        let c = Corpus::from_documents(vec![tokens("x y x y z"), tokens("y x y")]);
+        let mut contiguous = c.skipgram_strings(2, 0, 1);
+        let mut expected = c.decode_ngram_counts(&c.ngram_counts(2, 1));
+        contiguous.sort();
+        expected.sort();
+        assert_eq!(contiguous, expected);
+        // 1-skip-trigrams, which must not span the two documents.
+        let trigrams = c.skipgram_strings(3, 1, 2);
+        assert_eq!(trigrams, vec![(vec!["y", "x", "y"], 2)]);
+        assert!(c.skipgram_strings(3, 1, 1).iter().all(|entry| entry.0 != vec!["z", "y", "x"]));
+    }
+
+    #[test]
+    fn stream_counter_matches_corpus() {
+        let mut counter = SkipgramCounter::new(3, 2);
+        for token in "the cat sat on the mat".split_whitespace() {
+            counter.register(token);
+        }
+        counter.end_document();
+        for token in "the cat ate the mat".split_whitespace() {
+            counter.register(token);
+        }
+        let c = Corpus::from_documents(vec![tokens("the cat sat on the mat"), tokens("the cat ate the mat")]);
+        let mut expected = c.skipgram_strings(3, 2, 1);
+        let mut counted = counter.sorted_strings(1);
+        expected.sort();
+        counted.sort();
+        assert_eq!(counted, expected);
+    }
+}
@@ -1,23 +1,31 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::hash::Hash;
 
-pub struct Stringmap {
+// Interns tokens as consecutive codes. Tokens are usually strings, but any hashable type can be interned (the field
+// names predate that).
+#[derive(Clone)]
+pub struct Stringmap<T = String> {
     pub code_ctr: usize,
-    pub map: HashMap<String, usize>,
+    pub map: HashMap<T, usize>,
+    pub strings: Vec<T>,
 }
 
-impl Stringmap {
-    pub fn new() -> Stringmap {
+impl<T: Hash + Eq + Clone> Stringmap<T> {
+    pub fn new() -> Stringmap<T> {
         let code_ctr = 0;
         let map = HashMap::new();
-        Stringmap{ code_ctr: code_ctr, map: map }
+        let strings = Vec::new();
+        Stringmap{ code_ctr: code_ctr, map: map, strings: strings }
     }
 
-    pub fn add(&mut self, s: &String) -> usize {
+    pub fn add<Q: ?Sized + Hash + Eq + ToOwned<Owned = T>>(&mut self, s: &Q) -> usize where T: Borrow<Q> {
         let return_code: usize;
         match self.get(s) {
             Some(&code_value) => { return_code = code_value },
             None => {
-                self.map.insert(s.to_string(), self.code_ctr);
+                self.map.insert(s.to_owned(), self.code_ctr);
+                self.strings.push(s.to_owned());
                 return_code = self.code_ctr;
                 self.code_ctr += 1;
             }
@@ -25,11 +33,52 @@ impl Stringmap {
         return_code
     }
 
-    pub fn contains(&self, s: &String) -> bool {
+    pub fn contains<Q: ?Sized + Hash + Eq>(&self, s: &Q) -> bool where T: Borrow<Q> {
         self.map.contains_key(s)
     }
 
-    pub fn get(&self, s: &String) -> Option<&usize> {
+    pub fn get<Q: ?Sized + Hash + Eq>(&self, s: &Q) -> Option<&usize> where T: Borrow<Q> {
         self.map.get(s)
     }
+
+    pub fn decode(&self, code: usize) -> Option<&T> {
+        self.strings.get(code)
+    }
+
+    // Encodes a sequence of tokens to codes, or returns None if any token has not been assigned a code.
+    pub fn encode_tokens(&self, tokens: &[T]) -> Option<Vec<usize>> {
+        tokens.iter().map(|token| self.map.get(token).cloned()).collect()
+    }
+}
+
+impl Stringmap {
+    // Decodes a sequence of codes to strings, or returns None if any code has not been assigned.
+    pub fn decode_sequence(&self, seq: &[usize]) -> Option<Vec<&str>> {
+        let mut words: Vec<&str> = Vec::with_capacity(seq.len());
+        for code in seq.iter() {
+            match self.strings.get(*code) {
+                Some(word) => words.push(word.as_str()),
+                None => return None,
+            }
+        }
+        Some(words)
+    }
+
+    // Encodes a sequence of strings to codes. If any string is unknown, all of the unknown strings are returned as
+    // the error (in query order) so that callers can report them.
+    pub fn encode(&self, words: &[&str]) -> Result<Vec<usize>, Vec<String>> {
+        let mut seq: Vec<usize> = Vec::with_capacity(words.len());
+        let mut unknown: Vec<String> = Vec::new();
+        for word in words.iter() {
+            match self.map.get(*word) {
+                Some(&code) => seq.push(code),
+                None => unknown.push(word.to_string()),
+            }
+        }
+        if unknown.is_empty() {
+            Ok(seq)
+        } else {
+            Err(unknown)
+        }
+    }
 }
@@ -0,0 +1,278 @@
+// Linear-time suffix array construction by induced sorting (SA-IS; Nong, Zhang & Chan, 2009).
+//
+// The input is a sequence of integer codes drawn from 0..alphabet_size. The end of the sequence acts as a virtual
+// sentinel that is smaller than every code, so a suffix that is a prefix of another suffix sorts first. This is the
+// same ordering that sequence_compare imposes on suffixes.
+
+
+const EMPTY: usize = usize::MAX;
+
+
+// Returns the suffix array of a sequence whose values are all less than alphabet_size.
+pub fn suffix_array(sequence: &[usize], alphabet_size: usize) -> Vec<usize> {
+    let mut suffix = vec![EMPTY; sequence.len()];
+    sais(sequence, alphabet_size, &mut suffix);
+    suffix
+}
+
+
+// Returns the suffix array of a sequence divided into documents (given by their start positions), ordering each
+// suffix only by the tokens up to the end of its document. A separator smaller than every code is placed between
+// documents so that induced sorting sees each document end as the end of a suffix; separators are then dropped.
+pub fn suffix_array_documents(sequence: &[usize], alphabet_size: usize, documents: &[usize]) -> Vec<usize> {
+    if documents.len() <= 1 {
+        return suffix_array(sequence, alphabet_size);
+    }
+    // Shift codes up by one to make room for the separator (code 0).
+    let mut separated: Vec<usize> = Vec::with_capacity(sequence.len() + documents.len() - 1);
+    for doc in 0..documents.len() {
+        if doc > 0 {
+            separated.push(0);
+        }
+        let end = if doc + 1 < documents.len() { documents[doc + 1] } else { sequence.len() };
+        for &code in sequence[documents[doc]..end].iter() {
+            separated.push(code + 1);
+        }
+    }
+    let separated_suffix = suffix_array(&separated, alphabet_size + 1);
+    // Map positions in the separated sequence back to the original, where each position has been shifted right by
+    // the number of separators before it.
+    let mut original_pos: Vec<usize> = Vec::with_capacity(separated.len());
+    let mut num_separators = 0;
+    for (i, &code) in separated.iter().enumerate() {
+        if code == 0 {
+            num_separators += 1;
+            original_pos.push(EMPTY);
+        } else {
+            original_pos.push(i - num_separators);
+        }
+    }
+    let mut suffix: Vec<usize> = Vec::with_capacity(sequence.len());
+    for &p in separated_suffix.iter() {
+        if original_pos[p] != EMPTY {
+            suffix.push(original_pos[p]);
+        }
+    }
+    suffix
+}
+
+
+fn sais(s: &[usize], k: usize, sa: &mut [usize]) {
+    let n = s.len();
+    if n == 0 {
+        return;
+    }
+    if n == 1 {
+        sa[0] = 0;
+        return;
+    }
+    // Classify each position as S-type (true) or L-type (false). The last position is always L-type because it is
+    // followed by the sentinel.
+    let mut stype = vec![false; n];
+    for i in (0..(n - 1)).rev() {
+        stype[i] = s[i] < s[i + 1] || (s[i] == s[i + 1] && stype[i + 1]);
+    }
+    let is_lms = |i: usize| i > 0 && i < n && stype[i] && !stype[i - 1];
+    // Bucket sizes for each code.
+    let mut counts = vec![0; k];
+    for &c in s.iter() {
+        counts[c] += 1;
+    }
+    // Stage 1: place LMS positions at the tails of their buckets and induce an ordering of the LMS substrings.
+    for x in sa.iter_mut() {
+        *x = EMPTY;
+    }
+    {
+        let mut tails = bucket_tails(&counts);
+        for i in (1..n).rev() {
+            if is_lms(i) {
+                tails[s[i]] -= 1;
+                sa[tails[s[i]]] = i;
+            }
+        }
+    }
+    induce(s, &stype, &counts, sa);
+    // Compact the sorted LMS positions into the front of the array.
+    let mut num_lms = 0;
+    for i in 0..n {
+        let p = sa[i];
+        if is_lms(p) {
+            sa[num_lms] = p;
+            num_lms += 1;
+        }
+    }
+    for x in sa[num_lms..].iter_mut() {
+        *x = EMPTY;
+    }
+    // Name the LMS substrings; equal substrings share a name. Names are stored at sa[num_lms + p / 2], which cannot
+    // collide because LMS positions are at least two apart.
+    let mut name = 0;
+    let mut prev = EMPTY;
+    for i in 0..num_lms {
+        let p = sa[i];
+        if prev == EMPTY || !lms_substrings_equal(s, &stype, prev, p) {
+            name += 1;
+        }
+        prev = p;
+        sa[num_lms + p / 2] = name - 1;
+    }
+    // Gather the names in text order to form the reduced problem.
+    let mut reduced: Vec<usize> = Vec::with_capacity(num_lms);
+    for &r in sa[num_lms..].iter() {
+        if r != EMPTY {
+            reduced.push(r);
+        }
+    }
+    // Stage 2: sort the reduced problem, recursing if the names are not yet unique.
+    let mut reduced_sa = vec![EMPTY; num_lms];
+    if name < num_lms {
+        sais(&reduced, name, &mut reduced_sa);
+    } else {
+        for (i, &r) in reduced.iter().enumerate() {
+            reduced_sa[r] = i;
+        }
+    }
+    // Map the reduced suffix array back to LMS positions in the original sequence.
+    let mut lms_positions: Vec<usize> = Vec::with_capacity(num_lms);
+    for i in 1..n {
+        if is_lms(i) {
+            lms_positions.push(i);
+        }
+    }
+    // Stage 3: place the sorted LMS suffixes at their bucket tails (right to left) and induce the full ordering.
+    for x in sa.iter_mut() {
+        *x = EMPTY;
+    }
+    {
+        let mut tails = bucket_tails(&counts);
+        for i in (0..num_lms).rev() {
+            let p = lms_positions[reduced_sa[i]];
+            tails[s[p]] -= 1;
+            sa[tails[s[p]]] = p;
+        }
+    }
+    induce(s, &stype, &counts, sa);
+}
+
+
+// Induces L-type suffixes from left to right, then S-type suffixes from right to left.
+fn induce(s: &[usize], stype: &[bool], counts: &[usize], sa: &mut [usize]) {
+    let n = s.len();
+    // The suffix preceding the sentinel is L-type and is the smallest suffix in its bucket.
+    let mut heads = bucket_heads(counts);
+    sa[heads[s[n - 1]]] = n - 1;
+    heads[s[n - 1]] += 1;
+    for i in 0..n {
+        let p = sa[i];
+        if p != EMPTY && p > 0 && !stype[p - 1] {
+            let c = s[p - 1];
+            sa[heads[c]] = p - 1;
+            heads[c] += 1;
+        }
+    }
+    let mut tails = bucket_tails(counts);
+    for i in (0..n).rev() {
+        let p = sa[i];
+        if p != EMPTY && p > 0 && stype[p - 1] {
+            let c = s[p - 1];
+            tails[c] -= 1;
+            sa[tails[c]] = p - 1;
+        }
+    }
+}
+
+
+// Compares the LMS substrings starting at positions a and b (each running up to and including the next LMS position).
+fn lms_substrings_equal(s: &[usize], stype: &[bool], a: usize, b: usize) -> bool {
+    let n = s.len();
+    let is_lms = |i: usize| i > 0 && i < n && stype[i] && !stype[i - 1];
+    let mut d = 0;
+    loop {
+        // Running into the sentinel makes a substring unique.
+        if a + d == n || b + d == n {
+            return false;
+        }
+        if s[a + d] != s[b + d] || stype[a + d] != stype[b + d] {
+            return false;
+        }
+        if d > 0 && (is_lms(a + d) || is_lms(b + d)) {
+            return is_lms(a + d) && is_lms(b + d);
+        }
+        d += 1;
+    }
+}
+
+
+fn bucket_heads(counts: &[usize]) -> Vec<usize> {
+    let mut heads = Vec::with_capacity(counts.len());
+    let mut sum = 0;
+    for &c in counts.iter() {
+        heads.push(sum);
+        sum += c;
+    }
+    heads
+}
+
+
+fn bucket_tails(counts: &[usize]) -> Vec<usize> {
+    let mut tails = Vec::with_capacity(counts.len());
+    let mut sum = 0;
+    for &c in counts.iter() {
+        sum += c;
+        tails.push(sum);
+    }
+    tails
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::*;
+
+    use corpus::sequence::sequence_compare;
+
+    fn naive_suffix_array(sequence: &[usize]) -> Vec<usize> {
+        let mut suffix: Vec<usize> = (0..sequence.len()).collect();
+        suffix.sort_by(|a, b| sequence_compare(&sequence[*a..], &sequence[*b..]));
+        suffix
+    }
+
+    #[test]
+    fn matches_comparison_sort_on_random_sequences() {
+        for &ntypes in [1, 2, 3, 10, 100].iter() {
+            for ntokens in 0..60 {
+                let sequence: Vec<usize> = (0..ntokens).map(|_| rand::random::<usize>() % ntypes).collect();
+                assert_eq!(suffix_array(&sequence, ntypes), naive_suffix_array(&sequence));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_comparison_sort_on_repetitive_sequences() {
+        let mut sequence: Vec<usize> = Vec::new();
+        for _ in 0..50 {
+            sequence.extend_from_slice(&[0, 1, 2, 1, 2]);
+            if rand::random::<u8>() < 50 {
+                sequence.push(3);
+            }
+        }
+        assert_eq!(suffix_array(&sequence, 4), naive_suffix_array(&sequence));
+        let constant = vec![7; 300];
+        assert_eq!(suffix_array(&constant, 8), naive_suffix_array(&constant));
+    }
+
+    #[test]
+    fn document_suffixes_are_ordered_within_documents() {
+        let sequence: Vec<usize> = (0..300).map(|_| rand::random::<usize>() % 3).collect();
+        let documents = vec![0, 40, 40, 41, 150, 299];
+        let end = |p: usize| documents.iter().cloned().find(|&start| start > p).unwrap_or(sequence.len());
+        let suffix = suffix_array_documents(&sequence, 3, &documents);
+        assert_eq!(suffix.len(), sequence.len());
+        for i in 1..suffix.len() {
+            let (a, b) = (suffix[i - 1], suffix[i]);
+            assert!(sequence_compare(&sequence[a..end(a)], &sequence[b..end(b)]) != ::std::cmp::Ordering::Greater);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// Corpora over characters or bytes rather than whitespace tokens, for substring search, character n-grams and repeat
+// detection on unsegmented text. The wrapped corpus is a Corpus<char> or Corpus<u8>, so units are interned directly
+// and the usual code-level API applies; the source text and the byte offset of every unit are kept so that results
+// map back to slices of it.
+
+use std::hash::Hash;
+
+use corpus::corpus::{Corpus, SearchError};
+
+
+// A unit of text that a TextCorpus can be built over: char or u8.
+pub trait TextUnit: Hash + Ord + Clone {
+    // Splits text into units.
+    fn units(text: &str) -> Vec<Self>;
+
+    // Returns the byte offset of each unit of text.
+    fn offsets(text: &str) -> Vec<usize>;
+}
+
+
+impl TextUnit for char {
+    fn units(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn offsets(text: &str) -> Vec<usize> {
+        text.char_indices().map(|(offset, _)| offset).collect()
+    }
+}
+
+
+impl TextUnit for u8 {
+    fn units(text: &str) -> Vec<u8> {
+        text.bytes().collect()
+    }
+
+    fn offsets(text: &str) -> Vec<usize> {
+        (0..text.len()).collect()
+    }
+}
+
+
+pub struct TextCorpus<U> {
+    pub corpus: Corpus<U>,
+    // The documents, concatenated.
+    pub text: String,
+    // Byte offset into text of each unit, plus a final entry for the end of the text.
+    pub offsets: Vec<usize>,
+}
+
+
+impl<U: TextUnit> TextCorpus<U> {
+    pub fn new(text: &str) -> TextCorpus<U> {
+        TextCorpus::from_documents(&[text])
+    }
+
+    // Builds a corpus with one document per text. N-grams and searches never span two documents.
+    pub fn from_documents<S: AsRef<str>>(texts: &[S]) -> TextCorpus<U> {
+        let mut documents: Vec<Vec<U>> = Vec::with_capacity(texts.len());
+        let mut text = String::new();
+        let mut offsets: Vec<usize> = Vec::new();
+        for document in texts.iter() {
+            let document = document.as_ref();
+            documents.push(U::units(document));
+            offsets.extend(U::offsets(document).iter().map(|&offset| text.len() + offset));
+            text.push_str(document);
+        }
+        offsets.push(text.len());
+        TextCorpus { corpus: Corpus::from_token_documents(documents), text: text, offsets: offsets }
+    }
+
+    // Encodes a query string as units, returning SearchError::NotFound if it contains a unit that never occurs.
+    pub fn encode(&self, query: &str) -> Result<Vec<usize>, SearchError> {
+        self.corpus.stringmap.encode_tokens(&U::units(query)).ok_or(SearchError::NotFound)
+    }
+
+    // Returns the suffix array range of the suffixes beginning with a query string.
+    pub fn search(&self, query: &str) -> Result<(usize, usize), SearchError> {
+        let seq = self.encode(query)?;
+        if seq.is_empty() {
+            return Err(SearchError::InvalidPattern("empty query".to_string()));
+        }
+        self.corpus.search_binary(&seq).map_err(|_| SearchError::NotFound)
+    }
+
+    // Returns the byte offsets into text of every occurrence of a query string, in increasing order.
+    pub fn find(&self, query: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = match self.search(query) {
+            Ok((suffix_lo, suffix_hi)) => {
+                (suffix_lo..(suffix_hi + 1)).map(|i| self.offsets[self.corpus.suffix[i]]).collect()
+            },
+            Err(_) => Vec::new(),
+        };
+        found.sort();
+        found
+    }
+
+    // Returns the slice of text covered by length units starting at a sequence position, or None if (over bytes)
+    // the range would split a UTF-8 character.
+    pub fn slice(&self, seq_pos: usize, length: usize) -> Option<&str> {
+        self.text.get(self.offsets[seq_pos]..self.offsets[seq_pos + length])
+    }
+
+    // Returns each distinct n-unit string occurring at least min_count times with its frequency, in suffix array
+    // order. Over bytes, n-grams that split a UTF-8 character are left out.
+    pub fn ngram_counts(&self, n: usize, min_count: usize) -> Vec<(&str, usize)> {
+        self.corpus.ngram_position_counts(n, min_count).into_iter()
+            .filter_map(|(seq_pos, count)| self.slice(seq_pos, n).map(|s| (s, count)))
+            .collect()
+    }
+
+    // Returns the longest substring that occurs at least twice, if any.
+    pub fn longest_repeat(&self) -> Option<&str> {
+        self.corpus.longest_repeat_position().and_then(|(seq_pos, length)| self.slice(seq_pos, length))
+    }
+
+    // Returns the maximal repeats of at least min_length units occurring at least min_freq times, with their
+    // frequencies. Over bytes, repeats that split a UTF-8 character are left out.
+    pub fn maximal_repeats(&self, min_length: usize, min_freq: usize) -> Vec<(&str, usize)> {
+        self.corpus.maximal_repeats(min_length, min_freq).iter()
+            .filter_map(|repeat| {
+                self.slice(self.corpus.suffix[repeat.suffix_lo], repeat.length).map(|s| (s, repeat.frequency()))
+            })
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_char_corpus() {
+        let c = TextCorpus::<char>::from_documents(&["東京都と京都", "京都大学"]);
+        assert_eq!(c.corpus.sequence.len(), 10);
+        assert_eq!(c.corpus.stringmap.strings, vec!['と', '京', '大', '学', '東', '都']);
+        assert_eq!(c.find("京都"), vec![3, 12, 18]);
+        assert_eq!(c.search("都と京都大"), Err(SearchError::NotFound));
+        assert_eq!(c.search("大阪"), Err(SearchError::NotFound));
+        let mut bigrams = c.ngram_counts(2, 2);
+        bigrams.sort();
+        assert_eq!(bigrams, vec![("京都", 3)]);
+        assert_eq!(c.longest_repeat(), Some("京都"));
+        assert_eq!(c.slice(2, 3), Some("都と京"));
+    }
+
+    #[test]
+    fn check_byte_corpus() {
+        let c = TextCorpus::<u8>::new("fn a() {} fn b() {} é");
+        assert_eq!(c.corpus.sequence.len(), 22);
+        assert_eq!(c.corpus.stringmap.code_ctr, 11);
+        assert_eq!(c.find("() {}"), vec![4, 14]);
+        assert_eq!(c.find("é"), vec![20]);
+        assert_eq!(c.maximal_repeats(4, 2), vec![("() {} ", 2)]);
+        // The two bytes of "é" together form a bigram, but neither can be paired with the space before it.
+        let bigrams = c.ngram_counts(2, 1);
+        assert!(bigrams.contains(&("é", 1)));
+        assert!(bigrams.iter().all(|&(s, _)| s.len() == 2));
+    }
+}
@@ -0,0 +1,237 @@
+// A compressed full-text index (Ferragina & Manzini, 2000) for corpora too large to keep as sequence and suffix
+// arrays. It stores the Burrows-Wheeler transform of the corpus in a wavelet matrix (which doubles as the occurrence
+// table), the cumulative symbol counts, and a sample of the suffix array taken at every sample_rate-th text
+// position. Counting a pattern takes O(m log sigma) by backward search and locating each occurrence takes at most
+// sample_rate extra LF steps.
+//
+// The indexed text is the corpus with a separator between documents, so matches never span documents. Symbols are
+// shifted to make room below the token codes: 0 is the end-of-text sentinel, 1 the document separator and token code
+// c is stored as c + 2.
+
+use std::cmp;
+
+use corpus::corpus::{Corpus, SearchError};
+use corpus::index::SuffixIndex;
+use corpus::stringmap::Stringmap;
+use corpus::suffix_array::suffix_array;
+use fmindex::wavelet::{BitVector, WaveletMatrix};
+
+
+const SENTINEL: usize = 0;
+const SEPARATOR: usize = 1;
+const SHIFT: usize = 2;
+
+
+pub struct FmIndex {
+    bwt: WaveletMatrix,
+    // Number of symbols in the text smaller than each symbol.
+    c: Vec<usize>,
+    // Rows whose text position is sampled, and the sampled positions in row order.
+    sampled: BitVector,
+    samples: Vec<usize>,
+    documents: Vec<usize>,
+    pub stringmap: Stringmap,
+}
+
+
+impl FmIndex {
+    // Builds an FM-index from a corpus, sampling every sample_rate-th text position of the suffix array.
+    pub fn new(corpus: &Corpus, sample_rate: usize) -> FmIndex {
+        let sample_rate = cmp::max(sample_rate, 1);
+        // Build the separated text and its suffix array.
+        let mut text: Vec<usize> = Vec::with_capacity(corpus.sequence.len() + corpus.documents.len());
+        for doc in 0..corpus.num_documents() {
+            if doc > 0 {
+                text.push(SEPARATOR);
+            }
+            text.extend(corpus.document(doc).iter().map(|code| code + SHIFT));
+        }
+        let sigma = corpus.stringmap.code_ctr + SHIFT;
+        let text_suffix = suffix_array(&text, sigma);
+        // Row 0 of the conceptual rotation matrix is the empty suffix (the sentinel), followed by the suffix array.
+        let num_rows = text.len() + 1;
+        let mut bwt: Vec<usize> = Vec::with_capacity(num_rows);
+        let mut sampled: Vec<bool> = Vec::with_capacity(num_rows);
+        let mut samples: Vec<usize> = Vec::new();
+        for row in 0..num_rows {
+            let pos = if row == 0 { text.len() } else { text_suffix[row - 1] };
+            bwt.push(if pos == 0 { SENTINEL } else { text[pos - 1] });
+            let is_sampled = pos % sample_rate == 0 || pos == text.len();
+            sampled.push(is_sampled);
+            if is_sampled {
+                samples.push(pos);
+            }
+        }
+        let mut c = vec![0; sigma + 1];
+        for &symbol in bwt.iter() {
+            c[symbol + 1] += 1;
+        }
+        for symbol in 1..(sigma + 1) {
+            c[symbol] += c[symbol - 1];
+        }
+        FmIndex {
+            bwt: WaveletMatrix::new(&bwt),
+            c: c,
+            sampled: BitVector::new(&sampled),
+            samples: samples,
+            documents: corpus.documents.clone(),
+            stringmap: corpus.stringmap.clone(),
+        }
+    }
+
+    // Returns the range of the suffix array that points to a sequence, by backward search. The separated text sorts
+    // its suffixes exactly as Corpus::suffix does, after a row for the sentinel and one for each separator, so the
+    // rows are shifted past those to give the same range as Corpus::search_binary (the whole range for an empty
+    // sequence).
+    pub fn search(&self, seq: &[usize]) -> Result<(usize, usize), bool> {
+        let first = self.first_token_row();
+        if seq.is_empty() {
+            return if first < self.bwt.len() { Ok((0, self.bwt.len() - 1 - first)) } else { Err(false) };
+        }
+        let (mut start, mut end) = (0, self.bwt.len());
+        for &code in seq.iter().rev() {
+            let symbol = code + SHIFT;
+            if symbol + 1 >= self.c.len() {
+                return Err(false);
+            }
+            start = self.c[symbol] + self.bwt.rank(symbol, start);
+            end = self.c[symbol] + self.bwt.rank(symbol, end);
+            if start >= end {
+                return Err(false);
+            }
+        }
+        Ok((start - first, end - 1 - first))
+    }
+
+    // Returns the range of the suffix array that points to a sequence of words.
+    pub fn search_strings(&self, words: &[&str]) -> Result<(usize, usize), SearchError> {
+        self.search_words(words)
+    }
+
+    // Returns the corpus sequence positions of a range of the suffix array, sorted.
+    pub fn locate_range(&self, lo: usize, hi: usize) -> Vec<usize> {
+        let first = self.first_token_row();
+        let mut positions: Vec<usize> = ((lo + first)..(hi + first + 1))
+            .map(|row| self.sequence_position(self.text_position(row)))
+            .collect();
+        positions.sort();
+        positions
+    }
+
+    // Returns the first row whose suffix begins with a token rather than the sentinel or a separator.
+    fn first_token_row(&self) -> usize {
+        self.c[SHIFT]
+    }
+
+    // Returns the text position of a row by walking LF until a sampled row is reached.
+    fn text_position(&self, row: usize) -> usize {
+        let mut row = row;
+        let mut steps = 0;
+        while !self.sampled.get(row) {
+            let symbol = self.bwt.access(row);
+            row = self.c[symbol] + self.bwt.rank(symbol, row);
+            steps += 1;
+        }
+        self.samples[self.sampled.rank1(row)] + steps
+    }
+
+    // Converts a position in the separated text to a corpus sequence position.
+    fn sequence_position(&self, text_pos: usize) -> usize {
+        // Document d starts at text position documents[d] + d; find the last document starting at or before text_pos.
+        let (mut lo, mut hi) = (0, self.documents.len());
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.documents[mid] + mid <= text_pos {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        text_pos - (lo - 1)
+    }
+}
+
+
+impl SuffixIndex for FmIndex {
+    fn search(&self, seq: &[usize]) -> Result<(usize, usize), bool> {
+        FmIndex::search(self, seq)
+    }
+
+    fn positions(&self, seq: &[usize]) -> Vec<usize> {
+        match FmIndex::search(self, seq) {
+            Ok((lo, hi)) => self.locate_range(lo, hi),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn stringmap(&self) -> &Stringmap {
+        &self.stringmap
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::*;
+
+    #[test]
+    fn matches_corpus_counts_and_positions() {
+        let documents: Vec<Vec<String>> = (0..5).map(|d| {
+            (0..(d * 97 % 300)).map(|_| format!("{}", rand::random::<usize>() % 6)).collect()
+        }).collect();
+        let c = Corpus::from_documents(documents);
+        for &sample_rate in [1, 4, 32].iter() {
+            let fm = FmIndex::new(&c, sample_rate);
+            for n in 1..4 {
+                for seq_pos in 0..c.sequence.len() {
+                    if seq_pos + n > c.document_end(seq_pos) {
+                        continue;
+                    }
+                    let seq = &c.sequence[seq_pos..(seq_pos + n)];
+                    assert_eq!(fm.count(seq), c.count(seq));
+                    if seq_pos % 17 == 0 {
+                        assert_eq!(fm.positions(seq), c.positions(seq));
+                    }
+                }
+            }
+            assert_eq!(fm.count(&[c.stringmap.code_ctr + 5]), 0);
+        }
+    }
+
+    // Returns every query of up to two codes, including unknown codes and the empty query.
+    fn all_queries(num_codes: usize) -> Vec<Vec<usize>> {
+        let mut queries: Vec<Vec<usize>> = vec![Vec::new()];
+        for a in 0..(num_codes + 1) {
+            queries.push(vec![a]);
+            for b in 0..(num_codes + 1) {
+                queries.push(vec![a, b]);
+            }
+        }
+        queries
+    }
+
+    #[test]
+    fn search_agrees_with_corpus() {
+        let document = |text: &str| text.split_whitespace().map(|s| s.to_string()).collect::<Vec<String>>();
+        let corpora = [
+            Corpus::from_documents(vec![document("a b a c"), document(""), document("b a b"), document("a")]),
+            Corpus::new(document("x y x y x")),
+            Corpus::new(Vec::new()),
+        ];
+        for c in corpora.iter() {
+            let fm = FmIndex::new(c, 2);
+            for seq in all_queries(c.stringmap.code_ctr).iter() {
+                assert_eq!(fm.search(seq), c.search_binary(seq));
+                assert_eq!(SuffixIndex::positions(&fm, seq), SuffixIndex::positions(c, seq));
+            }
+            assert_eq!(fm.count(&[]), c.sequence.len());
+            if let Ok((lo, hi)) = fm.search(&[0]) {
+                let mut expected = c.suffix[lo..(hi + 1)].to_vec();
+                expected.sort();
+                assert_eq!(fm.locate_range(lo, hi), expected);
+            }
+        }
+    }
+}
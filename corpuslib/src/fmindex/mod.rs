@@ -0,0 +1,4 @@
+pub mod fm_index;
+pub mod wavelet;
+
+pub use self::fm_index::FmIndex;
@@ -0,0 +1,172 @@
+// Succinct rank structures used by the FM-index: a bit vector with constant-time rank and a wavelet matrix (Claude,
+// Navarro & Ordóñez, 2015) that answers rank and access queries over an integer sequence in O(log sigma) time.
+
+
+use std::cmp;
+
+
+// Number of 64-bit words covered by each cumulative rank sample.
+const WORDS_PER_BLOCK: usize = 8;
+
+
+pub struct BitVector {
+    words: Vec<u64>,
+    len: usize,
+    // Number of set bits before each block of WORDS_PER_BLOCK words.
+    block_ranks: Vec<usize>,
+}
+
+
+impl BitVector {
+    pub fn new(bits: &[bool]) -> BitVector {
+        let mut words = vec![0u64; bits.len().div_ceil(64)];
+        for (i, &bit) in bits.iter().enumerate() {
+            if bit {
+                words[i / 64] |= 1 << (i % 64);
+            }
+        }
+        let mut block_ranks: Vec<usize> = Vec::with_capacity(words.len() / WORDS_PER_BLOCK + 1);
+        let mut rank = 0;
+        for (i, word) in words.iter().enumerate() {
+            if i % WORDS_PER_BLOCK == 0 {
+                block_ranks.push(rank);
+            }
+            rank += word.count_ones() as usize;
+        }
+        block_ranks.push(rank);
+        BitVector { words: words, len: bits.len(), block_ranks: block_ranks }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        (self.words[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    // Returns the number of set bits in positions 0..i.
+    pub fn rank1(&self, i: usize) -> usize {
+        let word = i / 64;
+        let block = word / WORDS_PER_BLOCK;
+        let mut rank = self.block_ranks[block];
+        for w in (block * WORDS_PER_BLOCK)..word {
+            rank += self.words[w].count_ones() as usize;
+        }
+        if i % 64 != 0 {
+            rank += (self.words[word] & ((1u64 << (i % 64)) - 1)).count_ones() as usize;
+        }
+        rank
+    }
+
+    // Returns the number of unset bits in positions 0..i.
+    pub fn rank0(&self, i: usize) -> usize {
+        i - self.rank1(i)
+    }
+}
+
+
+pub struct WaveletMatrix {
+    levels: Vec<BitVector>,
+    // Number of zero bits at each level.
+    zeros: Vec<usize>,
+    len: usize,
+}
+
+
+impl WaveletMatrix {
+    pub fn new(values: &[usize]) -> WaveletMatrix {
+        let max = values.iter().cloned().max().unwrap_or(0);
+        let num_levels = cmp::max(1, (usize::BITS - max.leading_zeros()) as usize);
+        let mut levels: Vec<BitVector> = Vec::with_capacity(num_levels);
+        let mut zeros: Vec<usize> = Vec::with_capacity(num_levels);
+        let mut current: Vec<usize> = values.to_vec();
+        for level in 0..num_levels {
+            let shift = num_levels - 1 - level;
+            let bits: Vec<bool> = current.iter().map(|v| (v >> shift) & 1 == 1).collect();
+            // Stable partition: values with a zero bit move to the front for the next level.
+            let mut next: Vec<usize> = Vec::with_capacity(current.len());
+            next.extend(current.iter().filter(|v| (*v >> shift) & 1 == 0));
+            zeros.push(next.len());
+            next.extend(current.iter().filter(|v| (*v >> shift) & 1 == 1));
+            levels.push(BitVector::new(&bits));
+            current = next;
+        }
+        WaveletMatrix { levels: levels, zeros: zeros, len: values.len() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns the value at position i.
+    pub fn access(&self, i: usize) -> usize {
+        let mut i = i;
+        let mut value = 0;
+        for (level, bits) in self.levels.iter().enumerate() {
+            value <<= 1;
+            if bits.get(i) {
+                value |= 1;
+                i = self.zeros[level] + bits.rank1(i);
+            } else {
+                i = bits.rank0(i);
+            }
+        }
+        value
+    }
+
+    // Returns the number of occurrences of a value in positions 0..i.
+    pub fn rank(&self, value: usize, i: usize) -> usize {
+        let num_levels = self.levels.len();
+        if num_levels < usize::BITS as usize && value >> num_levels != 0 {
+            return 0;
+        }
+        let (mut start, mut end) = (0, i);
+        for (level, bits) in self.levels.iter().enumerate() {
+            if (value >> (num_levels - 1 - level)) & 1 == 1 {
+                start = self.zeros[level] + bits.rank1(start);
+                end = self.zeros[level] + bits.rank1(end);
+            } else {
+                start = bits.rank0(start);
+                end = bits.rank0(end);
+            }
+        }
+        end - start
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::*;
+
+    #[test]
+    fn rank_and_access_match_naive() {
+        for &sigma in [1, 2, 7, 300].iter() {
+            let values: Vec<usize> = (0..1500).map(|_| rand::random::<usize>() % sigma).collect();
+            let wm = WaveletMatrix::new(&values);
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(wm.access(i), value);
+            }
+            for value in 0..(sigma + 1) {
+                let mut count = 0;
+                for i in 0..(values.len() + 1) {
+                    assert_eq!(wm.rank(value, i), count);
+                    if i < values.len() && values[i] == value {
+                        count += 1;
+                    }
+                }
+            }
+        }
+    }
+}
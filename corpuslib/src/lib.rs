@@ -1,5 +1,18 @@
+extern crate memmap2;
+extern crate rand;
+extern crate regex;
+extern crate unicode_normalization;
+extern crate unicode_segmentation;
 extern crate walkdir;
 
+pub mod annotated;
+pub mod collocation;
+pub mod concordance;
 pub mod coocs;
 pub mod corpus;
+pub mod fmindex;
+pub mod lm;
+pub mod stats;
 pub mod stream;
+pub mod tokenize;
+pub mod vocab;
@@ -0,0 +1,140 @@
+// Unbounded-order ("infini-gram") next-token prediction. Instead of fixing an n-gram order, each query backs off to
+// the longest suffix of its context that occurs in the corpus with at least one following token, and returns the
+// distribution of the tokens that follow it. All counts come directly from suffix array ranges.
+
+use corpus::corpus::Corpus;
+
+
+// The tokens that follow a context in the corpus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NextTokens {
+    // Length of the longest context suffix that was found (0 means the unigram distribution was used).
+    pub backoff_length: usize,
+    // Number of occurrences of that suffix that are followed by a token.
+    pub total: usize,
+    // Following tokens and their counts, most frequent first.
+    pub counts: Vec<(usize, usize)>,
+}
+
+
+impl NextTokens {
+    pub fn prob(&self, code: usize) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        match self.counts.iter().find(|&&(c, _)| c == code) {
+            Some(&(_, count)) => count as f64 / self.total as f64,
+            None => 0.0,
+        }
+    }
+}
+
+
+pub struct InfiniGram<'a> {
+    corpus: &'a Corpus,
+}
+
+
+impl<'a> InfiniGram<'a> {
+    pub fn new(corpus: &'a Corpus) -> InfiniGram<'a> {
+        InfiniGram { corpus: corpus }
+    }
+
+    // Returns the distribution of tokens following the longest suffix of the context that occurs in the corpus and
+    // is followed by something. The suffix length is found by binary search, since any suffix of a suffix that has a
+    // continuation also has one.
+    pub fn next_tokens(&self, context: &[usize]) -> NextTokens {
+        let n = self.corpus.sequence.len();
+        if n == 0 {
+            return NextTokens { backoff_length: 0, total: 0, counts: Vec::new() };
+        }
+        let mut best = (0, (0, n - 1));
+        let (mut lo, mut hi) = (1, context.len());
+        while lo <= hi {
+            let mid = (lo + hi) / 2;
+            match self.continued_range(&context[(context.len() - mid)..]) {
+                Some(range) => {
+                    best = (mid, range);
+                    lo = mid + 1;
+                },
+                None => { hi = mid - 1; },
+            }
+        }
+        let (length, (suffix_lo, suffix_hi)) = best;
+        // Suffixes that continue past the context are sorted by their next token, so counts are runs.
+        let mut counts: Vec<(usize, usize)> = Vec::new();
+        let mut total = 0;
+        for suffix_pos in suffix_lo..(suffix_hi + 1) {
+            let seq_pos = self.corpus.suffix[suffix_pos];
+            if seq_pos + length >= self.corpus.document_end(seq_pos) {
+                continue;
+            }
+            let next = self.corpus.sequence[seq_pos + length];
+            match counts.last_mut() {
+                Some(&mut (code, ref mut count)) if code == next => { *count += 1; },
+                _ => counts.push((next, 1)),
+            }
+            total += 1;
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        NextTokens { backoff_length: length, total: total, counts: counts }
+    }
+
+    // Returns the next-token distribution for a context of strings, decoded to strings. Context words that are not
+    // in the corpus cut the context off, since no suffix containing them can occur.
+    pub fn next_token_strings(&self, context: &[&str]) -> (usize, Vec<(&'a str, usize)>) {
+        let mut codes: Vec<usize> = Vec::with_capacity(context.len());
+        for word in context.iter() {
+            match self.corpus.stringmap.get(*word) {
+                Some(&code) => codes.push(code),
+                None => codes.clear(),
+            }
+        }
+        let next = self.next_tokens(&codes);
+        let corpus = self.corpus;
+        let counts = next.counts.iter().map(|&(code, count)| (corpus.stringmap.strings[code].as_str(), count)).collect();
+        (next.backoff_length, counts)
+    }
+
+    // Returns the suffix array range for a sequence if at least one occurrence is followed by a token.
+    fn continued_range(&self, seq: &[usize]) -> Option<(usize, usize)> {
+        match self.corpus.search_binary(seq) {
+            Ok((suffix_lo, suffix_hi)) => {
+                // Occurrences that end at a document end sort first, so check the last one in the range.
+                let seq_pos = self.corpus.suffix[suffix_hi];
+                if seq_pos + seq.len() < self.corpus.document_end(seq_pos) {
+                    Some((suffix_lo, suffix_hi))
+                } else {
+                    None
+                }
+            },
+            Err(_) => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_to_longest_continued_suffix() {
+        let text = "a b c d a b c e x b c d y c";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        let ig = InfiniGram::new(&c);
+        let (length, counts) = ig.next_token_strings(&["z", "a", "b", "c"]);
+        assert_eq!(length, 3);
+        assert_eq!(counts, vec![("d", 1), ("e", 1)]);
+        let (length, counts) = ig.next_token_strings(&["q", "x", "b", "c"]);
+        assert_eq!(length, 3);
+        assert_eq!(counts, vec![("d", 1)]);
+        // "y c" only occurs at the end of the corpus, so the query backs off to "c".
+        let (length, counts) = ig.next_token_strings(&["y", "c"]);
+        assert_eq!(length, 1);
+        assert_eq!(counts, vec![("d", 2), ("e", 1)]);
+        let next = ig.next_tokens(&[]);
+        assert_eq!(next.backoff_length, 0);
+        assert_eq!(next.total, c.sequence.len());
+    }
+}
@@ -0,0 +1,239 @@
+// Interpolated modified Kneser-Ney n-gram language model (Chen & Goodman, 1998) estimated from corpus counts.
+//
+// The highest order uses raw n-gram counts and lower orders use continuation counts (the number of distinct tokens,
+// or document starts, that precede an n-gram). Each order has three discounts, for n-grams seen once, twice and
+// three or more times. The unigram distribution is interpolated with a uniform distribution over the vocabulary plus
+// one unknown word, so every token gets a non-zero probability. Documents are treated as sentences: histories never
+// reach back past the start of a document.
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+
+use corpus::corpus::Corpus;
+
+
+// Code used for words that are not in the corpus; it never occurs in any count table.
+const UNKNOWN: usize = usize::MAX;
+
+
+// Totals for the tokens that follow a context at one order.
+#[derive(Clone, Copy, Default)]
+struct ContextStats {
+    total: usize,
+    n1: usize,
+    n2: usize,
+    n3: usize,
+}
+
+
+pub struct KneserNey<'a> {
+    corpus: &'a Corpus,
+    order: usize,
+    // Counts for each order (index k - 1 holds k-grams), keyed by n-gram.
+    counts: Vec<HashMap<&'a [usize], usize>>,
+    // Context totals for each order, keyed by the k - 1 token context.
+    contexts: Vec<HashMap<&'a [usize], ContextStats>>,
+    // Discounts for counts of 1, 2 and 3+ at each order.
+    discounts: Vec<[f64; 3]>,
+}
+
+
+impl<'a> KneserNey<'a> {
+    // Estimates a model of the given order (at least 1) from a corpus.
+    pub fn new(corpus: &'a Corpus, order: usize) -> KneserNey<'a> {
+        let order = cmp::max(order, 1);
+        let mut counts: Vec<HashMap<&'a [usize], usize>> = Vec::with_capacity(order);
+        // The highest order keeps raw counts.
+        let top: HashMap<&'a [usize], usize> = corpus.ngram_counts(order, 1).into_iter().collect();
+        // Lower orders count distinct left contexts: every (k + 1)-gram contributes one to its final k tokens, and
+        // a document start counts as one more context.
+        for k in 1..order {
+            let mut continuation: HashMap<&'a [usize], usize> = HashMap::new();
+            for (ngram, _) in corpus.ngram_counts(k + 1, 1) {
+                *continuation.entry(&ngram[1..]).or_insert(0) += 1;
+            }
+            let mut at_start: HashSet<&'a [usize]> = HashSet::new();
+            for doc in 0..corpus.num_documents() {
+                let tokens = corpus.document(doc);
+                if tokens.len() >= k {
+                    at_start.insert(&tokens[..k]);
+                }
+            }
+            for ngram in at_start {
+                *continuation.entry(ngram).or_insert(0) += 1;
+            }
+            counts.push(continuation);
+        }
+        counts.push(top);
+        // Gather context totals and discounts for each order.
+        let mut contexts: Vec<HashMap<&'a [usize], ContextStats>> = Vec::with_capacity(order);
+        let mut discounts: Vec<[f64; 3]> = Vec::with_capacity(order);
+        for order_counts in counts.iter() {
+            let mut order_contexts: HashMap<&'a [usize], ContextStats> = HashMap::new();
+            let mut count_of_counts = [0usize; 5];
+            for (ngram, &count) in order_counts.iter() {
+                let stats = order_contexts.entry(&ngram[..(ngram.len() - 1)]).or_default();
+                stats.total += count;
+                match count {
+                    1 => stats.n1 += 1,
+                    2 => stats.n2 += 1,
+                    _ => stats.n3 += 1,
+                }
+                if count <= 4 {
+                    count_of_counts[count] += 1;
+                }
+            }
+            contexts.push(order_contexts);
+            discounts.push(estimate_discounts(&count_of_counts));
+        }
+        KneserNey { corpus: corpus, order: order, counts: counts, contexts: contexts, discounts: discounts }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    // Returns P(word | context) for codes, using at most the last order - 1 tokens of the context. Pass an
+    // out-of-range code for an unknown word.
+    pub fn prob(&self, context: &[usize], word: usize) -> f64 {
+        let k = cmp::min(context.len(), self.order - 1);
+        self.prob_interpolated(&context[(context.len() - k)..], word)
+    }
+
+    // Returns P(word | context) for strings. Unknown words are allowed anywhere.
+    pub fn prob_strings(&self, context: &[&str], word: &str) -> f64 {
+        let context: Vec<usize> = context.iter().map(|w| self.encode(w)).collect();
+        self.prob(&context, self.encode(word))
+    }
+
+    // Returns the natural log probability of a sentence, each word conditioned on the words before it.
+    pub fn sentence_log_prob(&self, words: &[&str]) -> f64 {
+        let codes: Vec<usize> = words.iter().map(|w| self.encode(w)).collect();
+        let mut log_prob = 0.0;
+        for i in 0..codes.len() {
+            log_prob += self.prob(&codes[..i], codes[i]).ln();
+        }
+        log_prob
+    }
+
+    // Returns the perplexity of held-out text, treating each line as a sentence of whitespace-separated words. Any
+    // iterator of lines can be used, including a LineStreamer over a directory of held-out files.
+    pub fn perplexity<I: Iterator<Item = String>>(&self, lines: I) -> f64 {
+        let mut log_prob = 0.0;
+        let mut num_words = 0;
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            log_prob += self.sentence_log_prob(&words);
+            num_words += words.len();
+        }
+        if num_words == 0 {
+            return 1.0;
+        }
+        (-log_prob / num_words as f64).exp()
+    }
+
+    fn prob_interpolated(&self, context: &[usize], word: usize) -> f64 {
+        let lower = if context.is_empty() {
+            1.0 / (self.corpus.stringmap.code_ctr + 1) as f64
+        } else {
+            self.prob_interpolated(&context[1..], word)
+        };
+        let k = context.len() + 1;
+        match self.contexts[k - 1].get(context) {
+            None => lower,
+            Some(stats) => {
+                let mut ngram: Vec<usize> = Vec::with_capacity(k);
+                ngram.extend_from_slice(context);
+                ngram.push(word);
+                let count = if word == UNKNOWN { 0 } else { *self.counts[k - 1].get(&ngram[..]).unwrap_or(&0) };
+                let d = self.discounts[k - 1];
+                let discounted = match count {
+                    0 => 0.0,
+                    1 => 1.0 - d[0],
+                    2 => 2.0 - d[1],
+                    c => c as f64 - d[2],
+                };
+                let gamma = d[0] * stats.n1 as f64 + d[1] * stats.n2 as f64 + d[2] * stats.n3 as f64;
+                (discounted + gamma * lower) / stats.total as f64
+            },
+        }
+    }
+
+    fn encode(&self, word: &str) -> usize {
+        match self.corpus.stringmap.get(word) {
+            Some(&code) => code,
+            None => UNKNOWN,
+        }
+    }
+}
+
+
+// Estimates the three discounts from the number of n-grams seen exactly 1, 2, 3 and 4 times. Falls back to fixed
+// discounts when there are too few n-grams to estimate them, or when any estimate is not positive: a zero discount
+// leaves no probability mass for unseen words in contexts whose continuations all share that count.
+fn estimate_discounts(count_of_counts: &[usize; 5]) -> [f64; 3] {
+    const FALLBACK: [f64; 3] = [0.5, 1.0, 1.5];
+    let (n1, n2, n3, n4) = (count_of_counts[1] as f64, count_of_counts[2] as f64, count_of_counts[3] as f64, count_of_counts[4] as f64);
+    if n1 == 0.0 || n2 == 0.0 || n3 == 0.0 || n4 == 0.0 {
+        return FALLBACK;
+    }
+    let y = n1 / (n1 + 2.0 * n2);
+    let d = [1.0 - 2.0 * y * n2 / n1, 2.0 - 3.0 * y * n3 / n2, 3.0 - 4.0 * y * n4 / n3];
+    if d.iter().any(|&d| d <= 0.0) {
+        return FALLBACK;
+    }
+    [d[0].min(1.0), d[1].min(2.0), d[2].min(3.0)]
+}
+
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    use super::*;
+
+    fn random_corpus(ntypes: usize, ntokens: usize) -> Corpus {
+        let tokens: Vec<String> = (0..ntokens).map(|_| format!("{}", rand::random::<usize>() % ntypes)).collect();
+        Corpus::new(tokens)
+    }
+
+    #[test]
+    fn distributions_sum_to_one() {
+        let c = random_corpus(20, 2000);
+        let lm = KneserNey::new(&c, 3);
+        for seq_pos in 0..50 {
+            let context = &c.sequence[seq_pos..(seq_pos + 2)];
+            let mut total = lm.prob(context, UNKNOWN);
+            for code in 0..c.stringmap.code_ctr {
+                total += lm.prob(context, code);
+            }
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn perplexity_prefers_seen_text() {
+        let text = "the cat sat on the mat . the dog sat on the rug . the cat lay on the rug .";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        let lm = KneserNey::new(&c, 3);
+        let seen = lm.perplexity(vec!["the cat sat on the rug .".to_string()].into_iter());
+        let unseen = lm.perplexity(vec!["rug the on . sat mat".to_string()].into_iter());
+        assert!(seen < unseen);
+        assert!(lm.prob_strings(&["sat", "on"], "the") > lm.prob_strings(&["sat", "on"], "cat"));
+        assert!(lm.prob_strings(&["sat", "on"], "unicorn") > 0.0);
+    }
+
+    #[test]
+    fn discounts_stay_positive() {
+        // Many n-grams seen four times against few seen three times would push the third discount below zero.
+        assert_eq!(estimate_discounts(&[0, 3, 1, 1, 4]), [0.5, 1.0, 1.5]);
+        assert!(estimate_discounts(&[0, 30, 10, 5, 2]).iter().all(|&d| d > 0.0));
+        // Bigram counts here are 4, 4, 4, 4, 3, 2, 1, 1, 1, so the context "a" has only a continuation seen four
+        // times and needs the third discount to reserve mass for unseen words.
+        let text = "a b c d e a b c d e a b c d e a b c d e f g f g h";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        let lm = KneserNey::new(&c, 2);
+        assert!(lm.prob_strings(&["a"], "unicorn") > 0.0);
+        assert!(lm.perplexity(vec!["a unicorn b".to_string()].into_iter()).is_finite());
+    }
+}
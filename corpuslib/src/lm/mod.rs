@@ -0,0 +1,5 @@
+pub mod infini_gram;
+pub mod kneser_ney;
+
+pub use self::infini_gram::{InfiniGram, NextTokens};
+pub use self::kneser_ney::KneserNey;
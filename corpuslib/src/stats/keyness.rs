@@ -0,0 +1,142 @@
+// Keyness: which words and n-grams are unusually frequent in a study corpus compared to a reference corpus. The two
+// corpora have separate Stringmaps, so n-grams are matched by their strings; an n-gram missing from one side simply
+// has frequency zero there.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use corpus::corpus::Corpus;
+
+
+// Frequency substituted for zero when taking the log ratio (Hardie, 2014).
+const ZERO_FREQUENCY: f64 = 0.5;
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyword<'a> {
+    pub ngram: Vec<&'a str>,
+    pub study_freq: usize,
+    pub reference_freq: usize,
+    // Dunning's log-likelihood (G²); always non-negative, so check is_positive for the direction.
+    pub log_likelihood: f64,
+    // Percentage difference between normalised frequencies (Gabrielatos & Marchi, 2012); infinite if the n-gram is
+    // absent from the reference corpus.
+    pub percent_diff: f64,
+    // Binary log of the ratio of relative frequencies.
+    pub log_ratio: f64,
+}
+
+
+impl<'a> Keyword<'a> {
+    // Whether the n-gram is relatively more frequent in the study corpus than in the reference corpus.
+    pub fn is_positive(&self) -> bool {
+        self.log_ratio > 0.0
+    }
+}
+
+
+pub struct Keyness<'a> {
+    study: &'a Corpus,
+    reference: &'a Corpus,
+}
+
+
+impl<'a> Keyness<'a> {
+    pub fn new(study: &'a Corpus, reference: &'a Corpus) -> Keyness<'a> {
+        Keyness { study: study, reference: reference }
+    }
+
+    // Returns the keyness of every word occurring at least min_freq times across both corpora, highest
+    // log-likelihood first.
+    pub fn words(&self, min_freq: usize) -> Vec<Keyword<'a>> {
+        self.ngrams(1, min_freq)
+    }
+
+    // Returns the keyness of every n-gram occurring at least min_freq times across both corpora, highest
+    // log-likelihood first. Corpus sizes are the total number of n-grams on each side.
+    pub fn ngrams(&self, n: usize, min_freq: usize) -> Vec<Keyword<'a>> {
+        let mut freqs: HashMap<Vec<&'a str>, (usize, usize)> = HashMap::new();
+        let study_total = add_counts(self.study, n, &mut freqs, true);
+        let reference_total = add_counts(self.reference, n, &mut freqs, false);
+        let mut keywords: Vec<Keyword<'a>> = freqs.into_iter()
+            .filter(|&(_, (a, b))| a + b >= cmp::max(min_freq, 1))
+            .map(|(ngram, (a, b))| keyword(ngram, a, b, study_total, reference_total))
+            .collect();
+        keywords.sort_by(|x, y| {
+            y.log_likelihood.partial_cmp(&x.log_likelihood).unwrap_or(cmp::Ordering::Equal)
+                .then_with(|| x.ngram.cmp(&y.ngram))
+        });
+        keywords
+    }
+}
+
+
+// Adds a corpus's n-gram counts to one side of the frequency table and returns the number of n-grams counted.
+fn add_counts<'a>(corpus: &'a Corpus, n: usize, freqs: &mut HashMap<Vec<&'a str>, (usize, usize)>, study: bool)
+    -> usize {
+    let mut total = 0;
+    for (ngram, count) in corpus.ngram_counts(n, 1) {
+        let entry = freqs.entry(corpus.decode(ngram)).or_insert((0, 0));
+        if study { entry.0 += count; } else { entry.1 += count; }
+        total += count;
+    }
+    total
+}
+
+
+fn keyword<'a>(ngram: Vec<&'a str>, a: usize, b: usize, c: usize, d: usize) -> Keyword<'a> {
+    let (fa, fb, fc, fd) = (a as f64, b as f64, c as f64, d as f64);
+    let e1 = fc * (fa + fb) / (fc + fd);
+    let e2 = fd * (fa + fb) / (fc + fd);
+    let term = |o: f64, e: f64| if o > 0.0 { o * (o / e).ln() } else { 0.0 };
+    let study_rel = if c > 0 { fa / fc } else { 0.0 };
+    let reference_rel = if d > 0 { fb / fd } else { 0.0 };
+    let percent_diff = if reference_rel > 0.0 {
+        100.0 * (study_rel - reference_rel) / reference_rel
+    } else if study_rel > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+    let smoothed = |f: f64, total: f64| if f > 0.0 { f / total } else { ZERO_FREQUENCY / total };
+    let log_ratio = if c > 0 && d > 0 { (smoothed(fa, fc) / smoothed(fb, fd)).log2() } else { 0.0 };
+    Keyword {
+        ngram: ngram,
+        study_freq: a,
+        reference_freq: b,
+        log_likelihood: 2.0 * (term(fa, e1) + term(fb, e2)),
+        percent_diff: percent_diff,
+        log_ratio: log_ratio,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus(text: &str) -> Corpus {
+        Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn check_keyness() {
+        let study = corpus("the cell divides and the cell grows and the protein folds");
+        let reference = corpus("the man walks and the dog runs and the man sleeps and the sun sets");
+        let keyness = Keyness::new(&study, &reference);
+        let words = keyness.words(1);
+        assert_eq!(words[0].ngram, vec!["cell"]);
+        assert!(words[0].is_positive());
+        assert_eq!(words[0].reference_freq, 0);
+        assert!(words[0].percent_diff.is_infinite());
+        let man = words.iter().find(|k| k.ngram == vec!["man"]).unwrap();
+        assert_eq!((man.study_freq, man.reference_freq), (0, 2));
+        assert!(!man.is_positive());
+        let the = words.iter().find(|k| k.ngram == vec!["the"]).unwrap();
+        assert_eq!((the.study_freq, the.reference_freq), (3, 4));
+        assert!((the.percent_diff - 100.0 * ((3.0 / 11.0) / (4.0 / 15.0) - 1.0)).abs() < 1e-9);
+        let bigrams = keyness.ngrams(2, 2);
+        assert_eq!(bigrams[0].ngram, vec!["the", "cell"]);
+        assert!(bigrams.iter().all(|k| k.study_freq + k.reference_freq >= 2));
+    }
+}
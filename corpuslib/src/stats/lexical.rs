@@ -0,0 +1,206 @@
+// Vocabulary and lexical statistics for a Corpus, writable as JSON or TSV.
+
+use std::io;
+use std::io::Write;
+
+use corpus::corpus::Corpus;
+
+
+// A least-squares fit of log frequency against log rank: frequency ~ rank ^ -exponent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZipfFit {
+    pub exponent: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexicalStats {
+    pub tokens: usize,
+    pub types: usize,
+    pub type_token_ratio: f64,
+    pub hapax_legomena: usize,
+    pub dis_legomena: usize,
+    // (frequency, number of types occurring with that frequency), in increasing order of frequency.
+    pub spectrum: Vec<(usize, usize)>,
+    pub zipf: ZipfFit,
+    // (tokens read, distinct types seen so far), sampled at evenly spaced points along the sequence.
+    pub heaps: Vec<(usize, usize)>,
+}
+
+
+impl LexicalStats {
+    // Computes statistics for a corpus, sampling the Heaps' law curve at heaps_points evenly spaced positions.
+    pub fn new(corpus: &Corpus, heaps_points: usize) -> LexicalStats {
+        let sequence = &corpus.sequence;
+        let mut freqs = vec![0; corpus.stringmap.strings.len()];
+        for &code in sequence.iter() {
+            freqs[code] += 1;
+        }
+        let mut ranked: Vec<usize> = freqs.iter().cloned().filter(|&f| f > 0).collect();
+        ranked.sort_by(|a, b| b.cmp(a));
+        let tokens = sequence.len();
+        let types = ranked.len();
+        // The frequency spectrum, read off the ranked frequencies from least to most frequent.
+        let mut spectrum: Vec<(usize, usize)> = Vec::new();
+        for &f in ranked.iter().rev() {
+            match spectrum.last_mut() {
+                Some(&mut (freq, ref mut count)) if freq == f => { *count += 1; continue; },
+                _ => {},
+            }
+            spectrum.push((f, 1));
+        }
+        let types_with = |f: usize| spectrum.iter().find(|&&(freq, _)| freq == f).map_or(0, |&(_, count)| count);
+        let hapax_legomena = types_with(1);
+        let dis_legomena = types_with(2);
+        LexicalStats {
+            tokens: tokens,
+            types: types,
+            type_token_ratio: if tokens == 0 { 0.0 } else { types as f64 / tokens as f64 },
+            hapax_legomena: hapax_legomena,
+            dis_legomena: dis_legomena,
+            spectrum: spectrum,
+            zipf: zipf_fit(&ranked),
+            heaps: heaps_curve(sequence, freqs.len(), heaps_points),
+        }
+    }
+
+    pub fn write_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"tokens\": {},", self.tokens)?;
+        writeln!(w, "  \"types\": {},", self.types)?;
+        writeln!(w, "  \"type_token_ratio\": {},", json_number(self.type_token_ratio))?;
+        writeln!(w, "  \"hapax_legomena\": {},", self.hapax_legomena)?;
+        writeln!(w, "  \"dis_legomena\": {},", self.dis_legomena)?;
+        writeln!(w, "  \"zipf\": {{\"exponent\": {}, \"intercept\": {}, \"r_squared\": {}}},",
+                      json_number(self.zipf.exponent), json_number(self.zipf.intercept),
+                      json_number(self.zipf.r_squared))?;
+        let spectrum: Vec<String> = self.spectrum.iter()
+            .map(|&(freq, count)| format!("{{\"frequency\": {}, \"types\": {}}}", freq, count))
+            .collect();
+        writeln!(w, "  \"spectrum\": [{}],", spectrum.join(", "))?;
+        let heaps: Vec<String> = self.heaps.iter()
+            .map(|&(tokens, types)| format!("{{\"tokens\": {}, \"types\": {}}}", tokens, types))
+            .collect();
+        writeln!(w, "  \"heaps\": [{}]", heaps.join(", "))?;
+        writeln!(w, "}}")
+    }
+
+    // Writes one row per statistic. Scalars are "name<TAB>value"; the spectrum and Heaps' curve are written as
+    // "spectrum<TAB>frequency<TAB>types" and "heaps<TAB>tokens<TAB>types" rows.
+    pub fn write_tsv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "tokens\t{}", self.tokens)?;
+        writeln!(w, "types\t{}", self.types)?;
+        writeln!(w, "type_token_ratio\t{}", self.type_token_ratio)?;
+        writeln!(w, "hapax_legomena\t{}", self.hapax_legomena)?;
+        writeln!(w, "dis_legomena\t{}", self.dis_legomena)?;
+        writeln!(w, "zipf_exponent\t{}", self.zipf.exponent)?;
+        writeln!(w, "zipf_intercept\t{}", self.zipf.intercept)?;
+        writeln!(w, "zipf_r_squared\t{}", self.zipf.r_squared)?;
+        for &(freq, count) in self.spectrum.iter() {
+            writeln!(w, "spectrum\t{}\t{}", freq, count)?;
+        }
+        for &(tokens, types) in self.heaps.iter() {
+            writeln!(w, "heaps\t{}\t{}", tokens, types)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        self.write_json(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    pub fn to_tsv(&self) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        self.write_tsv(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+
+// Fits log(frequency) = intercept - exponent * log(rank) by least squares over frequencies sorted in decreasing order.
+fn zipf_fit(ranked: &[usize]) -> ZipfFit {
+    if ranked.len() < 2 {
+        return ZipfFit { exponent: 0.0, intercept: 0.0, r_squared: 0.0 };
+    }
+    let n = ranked.len() as f64;
+    let xs: Vec<f64> = (1..(ranked.len() + 1)).map(|rank| (rank as f64).ln()).collect();
+    let ys: Vec<f64> = ranked.iter().map(|&f| (f as f64).ln()).collect();
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    let mut syy = 0.0;
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        sxx += (x - mean_x) * (x - mean_x);
+        sxy += (x - mean_x) * (y - mean_y);
+        syy += (y - mean_y) * (y - mean_y);
+    }
+    let slope = sxy / sxx;
+    // A perfectly flat distribution is fitted exactly by a zero slope.
+    let r_squared = if syy == 0.0 { 1.0 } else { (sxy * sxy) / (sxx * syy) };
+    ZipfFit { exponent: -slope, intercept: mean_y - slope * mean_x, r_squared: r_squared }
+}
+
+
+// Counts the distinct types seen after each of num_points evenly spaced prefixes of the sequence, ending with the
+// whole sequence.
+fn heaps_curve(sequence: &[usize], alphabet_size: usize, num_points: usize) -> Vec<(usize, usize)> {
+    let num_points = num_points.min(sequence.len());
+    let mut curve: Vec<(usize, usize)> = Vec::with_capacity(num_points);
+    let mut seen = vec![false; alphabet_size];
+    let mut types = 0;
+    let mut i = 0;
+    for point in 1..(num_points + 1) {
+        let end = point * sequence.len() / num_points;
+        while i < end {
+            if !seen[sequence[i]] {
+                seen[sequence[i]] = true;
+                types += 1;
+            }
+            i += 1;
+        }
+        curve.push((end, types));
+    }
+    curve
+}
+
+
+// JSON has no representation for NaN or infinity.
+fn json_number(x: f64) -> String {
+    if x.is_finite() { format!("{}", x) } else { "null".to_string() }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_lexical_stats() {
+        let text = "a a a a b b c c d e";
+        let c = Corpus::new(text.split_whitespace().map(|s| s.to_string()).collect());
+        let stats = LexicalStats::new(&c, 5);
+        assert_eq!(stats.tokens, 10);
+        assert_eq!(stats.types, 5);
+        assert_eq!(stats.type_token_ratio, 0.5);
+        assert_eq!(stats.hapax_legomena, 2);
+        assert_eq!(stats.dis_legomena, 2);
+        assert_eq!(stats.spectrum, vec![(1, 2), (2, 2), (4, 1)]);
+        assert_eq!(stats.heaps, vec![(2, 1), (4, 1), (6, 2), (8, 3), (10, 5)]);
+        assert!(stats.zipf.exponent > 0.0);
+        assert!(stats.to_json().contains("\"spectrum\": [{\"frequency\": 1, \"types\": 2}"));
+        assert!(stats.to_tsv().contains("heaps\t10\t5\n"));
+    }
+
+    #[test]
+    fn zipf_fit_recovers_exponent() {
+        let ranked: Vec<usize> = (1..50).map(|rank| (10000.0 / (rank as f64)).round() as usize).collect();
+        let fit = zipf_fit(&ranked);
+        assert!((fit.exponent - 1.0).abs() < 0.01);
+        assert!(fit.r_squared > 0.99);
+    }
+}
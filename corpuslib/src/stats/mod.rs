@@ -0,0 +1,5 @@
+pub mod keyness;
+pub mod lexical;
+
+pub use self::keyness::{Keyness, Keyword};
+pub use self::lexical::{LexicalStats, ZipfFit};
@@ -1,5 +1,6 @@
 use std::fs::{File};
 use std::io::{BufRead, BufReader, Error, ErrorKind};
+use std::path::Path;
 use walkdir::{DirEntry, WalkDir};
 
 
@@ -36,6 +37,21 @@ impl LineStreamer {
         ls
     }
 
+    // Returns the index (in directory walk order) of the file the most recently returned line was read from. The
+    // index changes whenever the streamer moves on to a new file.
+    pub fn file_index(&self) -> usize {
+        self.entries_ctr.saturating_sub(1)
+    }
+
+    // Returns the path of the file the most recently returned line was read from.
+    pub fn path(&self) -> Option<&Path> {
+        if self.entries_ctr > 0 {
+            Some(self.entries[self.entries_ctr - 1].path())
+        } else {
+            None
+        }
+    }
+
     fn buffer_next(&mut self) {
         /*
         Advances the internal buffer to the next valid one. If there isn't one then the buffer is set to an error.
@@ -0,0 +1,8 @@
+pub mod normalize;
+pub mod sentences;
+pub mod tokenizer;
+
+pub use self::normalize::{Normalization, Normalized, UnicodeForm};
+pub use self::sentences::{SentenceSplitter, Sentences};
+pub use self::tokenizer::{PunctuationTokenizer, RegexTokenizer, TokenizedLines, Tokenizer, UnicodeWordTokenizer,
+                          WhitespaceTokenizer};
@@ -0,0 +1,84 @@
+// Token normalization, applied after tokenizing.
+
+use unicode_normalization::UnicodeNormalization;
+
+use tokenize::tokenizer::Tokenizer;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnicodeForm {
+    // Canonical composition: the same text written with different code points becomes identical.
+    Nfc,
+    // Compatibility composition: additionally folds ligatures, full-width forms, superscripts and so on.
+    Nfkc,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Normalization {
+    pub lowercase: bool,
+    pub unicode_form: Option<UnicodeForm>,
+    // Replaces every ASCII digit with 0, so that numbers of the same shape share a token. Combine with NFKC to fold
+    // full-width digits as well.
+    pub fold_digits: bool,
+}
+
+
+impl Normalization {
+    pub fn apply(&self, token: &str) -> String {
+        let mut normalized: String = match self.unicode_form {
+            Some(UnicodeForm::Nfc) => token.nfc().collect(),
+            Some(UnicodeForm::Nfkc) => token.nfkc().collect(),
+            None => token.to_string(),
+        };
+        if self.lowercase {
+            normalized = normalized.to_lowercase();
+        }
+        if self.fold_digits {
+            normalized = normalized.chars().map(|c| if c.is_ascii_digit() { '0' } else { c }).collect();
+        }
+        normalized
+    }
+}
+
+
+// A tokenizer whose tokens are normalized before being returned.
+#[derive(Clone, Debug)]
+pub struct Normalized<T> {
+    pub tokenizer: T,
+    pub normalization: Normalization,
+}
+
+
+impl<T: Tokenizer> Normalized<T> {
+    pub fn new(tokenizer: T, normalization: Normalization) -> Normalized<T> {
+        Normalized { tokenizer: tokenizer, normalization: normalization }
+    }
+}
+
+
+impl<T: Tokenizer> Tokenizer for Normalized<T> {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        self.tokenizer.tokenize(text).iter().map(|token| self.normalization.apply(token)).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokenize::tokenizer::WhitespaceTokenizer;
+
+    #[test]
+    fn check_normalization() {
+        let n = Normalization { lowercase: true, unicode_form: Some(UnicodeForm::Nfkc), fold_digits: true };
+        assert_eq!(n.apply("Ｆｉｌｅ２０２６"), "file0000");
+        assert_eq!(n.apply("ﬁne"), "fine");
+        let nfc = Normalization { unicode_form: Some(UnicodeForm::Nfc), ..Normalization::default() };
+        assert_eq!(nfc.apply("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(nfc.apply("ﬁne"), "ﬁne");
+        let tokenizer = Normalized::new(WhitespaceTokenizer, Normalization { lowercase: true, ..Normalization::default() });
+        assert_eq!(tokenizer.tokenize("The Cat"), vec!["the", "cat"]);
+    }
+}
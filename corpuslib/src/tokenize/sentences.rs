@@ -0,0 +1,204 @@
+// Rule-based sentence segmentation. A sentence ends at a run of terminal punctuation (plus any closing quotes or
+// brackets) that is followed by whitespace and then something that can start a sentence. A full stop does not end a
+// sentence after a known abbreviation, a single-letter initial, or when the next word starts in lower case.
+
+use std::collections::HashSet;
+
+
+// Abbreviations (lower case, without their final full stop) that are usually followed by more of the same sentence.
+const ENGLISH_ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "rev", "gen", "col", "lt", "sgt", "capt", "gov", "sen",
+    "rep", "vs", "etc", "e.g", "i.e", "cf", "al", "approx", "dept", "est", "fig", "inc", "ltd", "co", "corp", "no",
+    "vol", "p", "pp", "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec", "u.s",
+    "u.k",
+];
+const GERMAN_ABBREVIATIONS: &[&str] = &[
+    "z.b", "bzw", "usw", "etc", "ca", "dr", "prof", "hr", "fr", "nr", "s", "vgl", "d.h", "u.a", "z.t", "evtl", "ggf",
+    "inkl", "bspw", "str", "jh", "mio", "mrd",
+];
+const FRENCH_ABBREVIATIONS: &[&str] = &[
+    "m", "mm", "mme", "mlle", "dr", "pr", "etc", "cf", "p.ex", "env", "av", "apr", "j.-c", "n°", "no", "vol", "st",
+    "ste",
+];
+const SPANISH_ABBREVIATIONS: &[&str] = &[
+    "sr", "sra", "srta", "dr", "dra", "ud", "uds", "etc", "p.ej", "pág", "núm", "aprox", "av", "ee.uu", "vol",
+];
+
+// Characters that end a sentence, and those (from CJK scripts) that end one without needing a following space.
+const TERMINATORS: &[char] = &['.', '!', '?', '…', '‽'];
+const WIDE_TERMINATORS: &[char] = &['。', '！', '？'];
+// Closing punctuation that belongs to the sentence it follows.
+const CLOSERS: &[char] = &['"', '\'', ')', ']', '}', '»', '”', '’', '」', '』'];
+// Opening punctuation that can start a sentence.
+const OPENERS: &[char] = &['"', '\'', '(', '[', '{', '«', '“', '‘', '¿', '¡', '「', '『', '-', '—'];
+
+
+#[derive(Clone, Debug)]
+pub struct SentenceSplitter {
+    abbreviations: HashSet<String>,
+}
+
+
+impl SentenceSplitter {
+    // Creates a splitter that knows the given abbreviations (matched case-insensitively, without their final full
+    // stop).
+    pub fn new<S: AsRef<str>>(abbreviations: &[S]) -> SentenceSplitter {
+        SentenceSplitter { abbreviations: abbreviations.iter().map(|a| a.as_ref().to_lowercase()).collect() }
+    }
+
+    // Returns a splitter with the abbreviations of a language given by its ISO 639-1 code ("en", "de", "fr" or
+    // "es"), or None if the language is not supported.
+    pub fn for_language(language: &str) -> Option<SentenceSplitter> {
+        match language {
+            "en" => Some(SentenceSplitter::new(ENGLISH_ABBREVIATIONS)),
+            "de" => Some(SentenceSplitter::new(GERMAN_ABBREVIATIONS)),
+            "fr" => Some(SentenceSplitter::new(FRENCH_ABBREVIATIONS)),
+            "es" => Some(SentenceSplitter::new(SPANISH_ABBREVIATIONS)),
+            _ => None,
+        }
+    }
+
+    pub fn add_abbreviation(&mut self, abbreviation: &str) {
+        self.abbreviations.insert(abbreviation.to_lowercase());
+    }
+
+    // Splits text into sentences, trimmed of surrounding whitespace.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut sentences: Vec<&'a str> = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i].1;
+            if !TERMINATORS.contains(&c) && !WIDE_TERMINATORS.contains(&c) {
+                i += 1;
+                continue;
+            }
+            // Take the whole run of terminators and closing punctuation.
+            let run_start = i;
+            while i < chars.len() && (TERMINATORS.contains(&chars[i].1) || WIDE_TERMINATORS.contains(&chars[i].1)
+                                      || CLOSERS.contains(&chars[i].1)) {
+                i += 1;
+            }
+            let end = if i < chars.len() { chars[i].0 } else { text.len() };
+            let wide = chars[run_start..i].iter().any(|&(_, c)| WIDE_TERMINATORS.contains(&c));
+            if self.is_boundary(text, &chars, run_start, i, wide) {
+                push_trimmed(&mut sentences, &text[start..end]);
+                start = end;
+            }
+        }
+        push_trimmed(&mut sentences, &text[start..]);
+        sentences
+    }
+
+    // Decides whether the run of punctuation chars[run_start..run_end] ends a sentence.
+    fn is_boundary(&self, text: &str, chars: &[(usize, char)], run_start: usize, run_end: usize, wide: bool) -> bool {
+        // Find the next non-space character; the end of the text always ends a sentence.
+        let mut next = run_end;
+        while next < chars.len() && chars[next].1.is_whitespace() {
+            next += 1;
+        }
+        if next == chars.len() {
+            return true;
+        }
+        if !wide && next == run_end {
+            return false;
+        }
+        let following = chars[next].1;
+        if following.is_lowercase() || !(following.is_alphanumeric() || OPENERS.contains(&following) || wide) {
+            return false;
+        }
+        // A single full stop may belong to an abbreviation or an initial rather than end the sentence.
+        if chars[run_start].1 == '.' && (run_start + 1 == run_end || !TERMINATORS.contains(&chars[run_start + 1].1)) {
+            let mut k = run_start;
+            while k > 0 && !chars[k - 1].1.is_whitespace() {
+                k -= 1;
+            }
+            let word_start = chars[k].0;
+            let word = text[word_start..chars[run_start].0].trim_start_matches(|c: char| OPENERS.contains(&c));
+            let initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+            if initial || self.abbreviations.contains(&word.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+
+fn push_trimmed<'a>(sentences: &mut Vec<&'a str>, sentence: &'a str) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+}
+
+
+// Splits each line of a stream of lines (for example a LineStreamer, where a line may hold a whole paragraph) into
+// sentences, yielding one sentence at a time. Sentences never continue from one line into the next.
+pub struct Sentences<I> {
+    lines: I,
+    splitter: SentenceSplitter,
+    pending: Vec<String>,
+}
+
+
+impl<I: Iterator<Item = String>> Sentences<I> {
+    pub fn new(lines: I, splitter: SentenceSplitter) -> Sentences<I> {
+        Sentences { lines: lines, splitter: splitter, pending: Vec::new() }
+    }
+}
+
+
+impl<I: Iterator<Item = String>> Iterator for Sentences<I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while self.pending.is_empty() {
+            let line = self.lines.next()?;
+            self.pending = self.splitter.split(&line).iter().rev().map(|s| s.to_string()).collect();
+        }
+        self.pending.pop()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_english_sentences() {
+        let splitter = SentenceSplitter::for_language("en").unwrap();
+        let text = "Mr. Smith paid $3.50 for it, i.e. too much. \"Really?!\" she asked. J. R. R. Tolkien wrote it... \
+                    (It sold well.) The end";
+        assert_eq!(splitter.split(text), vec![
+            "Mr. Smith paid $3.50 for it, i.e. too much.",
+            "\"Really?!\" she asked.",
+            "J. R. R. Tolkien wrote it...",
+            "(It sold well.)",
+            "The end",
+        ]);
+        assert_eq!(splitter.split("See fig. 3 and e.g. the rest.  "), vec!["See fig. 3 and e.g. the rest."]);
+        assert_eq!(splitter.split(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn check_other_languages() {
+        let german = SentenceSplitter::for_language("de").unwrap();
+        assert_eq!(german.split("Das ist z.B. gut. Vgl. Abschnitt 2. Ende."),
+                   vec!["Das ist z.B. gut.", "Vgl. Abschnitt 2.", "Ende."]);
+        let mut custom = SentenceSplitter::new(&["approx"]);
+        custom.add_abbreviation("Abt");
+        assert_eq!(custom.split("Ask Abt. Lee. 今日は晴れ。明日は雨。"), vec!["Ask Abt. Lee.", "今日は晴れ。", "明日は雨。"]);
+        assert!(SentenceSplitter::for_language("xx").is_none());
+        // Spaces wider than one byte before an abbreviation or initial.
+        let english = SentenceSplitter::for_language("en").unwrap();
+        assert_eq!(english.split("See\u{a0}Dr. Who ran. Then left."), vec!["See\u{a0}Dr. Who ran.", "Then left."]);
+        assert_eq!(english.split("By\u{3000}J. Smith. Then\u{2009}Mr. Lee."),
+                   vec!["By\u{3000}J. Smith.", "Then\u{2009}Mr. Lee."]);
+        let lines = vec!["One. Two.".to_string(), "".to_string(), "Three".to_string()];
+        let sentences: Vec<String> = Sentences::new(lines.into_iter(), custom).collect();
+        assert_eq!(sentences, vec!["One.", "Two.", "Three"]);
+    }
+}
@@ -0,0 +1,129 @@
+// Tokenizers split a line of text into tokens. Whitespace splitting is what the rest of the library assumes when no
+// tokenizer is given.
+
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+
+pub trait Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String>;
+}
+
+
+// Splits on runs of whitespace.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WhitespaceTokenizer;
+
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.split_whitespace().map(|s| s.to_string()).collect()
+    }
+}
+
+
+// Takes every non-overlapping match of a regular expression as a token, e.g. r"\w+|[^\w\s]".
+#[derive(Clone, Debug)]
+pub struct RegexTokenizer {
+    regex: Regex,
+}
+
+
+impl RegexTokenizer {
+    pub fn new(pattern: &str) -> Result<RegexTokenizer, ::regex::Error> {
+        Ok(RegexTokenizer { regex: Regex::new(pattern)? })
+    }
+}
+
+
+impl Tokenizer for RegexTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        self.regex.find_iter(text).map(|m| m.as_str().to_string()).collect()
+    }
+}
+
+
+// Splits at Unicode word boundaries (UAX #29), keeping only the words: tokens containing a letter or digit.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnicodeWordTokenizer;
+
+
+impl Tokenizer for UnicodeWordTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        text.unicode_words().map(|s| s.to_string()).collect()
+    }
+}
+
+
+// Splits on whitespace and then separates every punctuation or symbol character into a token of its own, so
+// "(don't)" becomes "(", "don", "'", "t", ")".
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PunctuationTokenizer;
+
+
+impl Tokenizer for PunctuationTokenizer {
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        let mut tokens: Vec<String> = Vec::new();
+        for chunk in text.split_whitespace() {
+            let mut word = String::new();
+            for c in chunk.chars() {
+                if c.is_alphanumeric() {
+                    word.push(c);
+                } else {
+                    if !word.is_empty() {
+                        tokens.push(word);
+                        word = String::new();
+                    }
+                    tokens.push(c.to_string());
+                }
+            }
+            if !word.is_empty() {
+                tokens.push(word);
+            }
+        }
+        tokens
+    }
+}
+
+
+// Tokenizes each line of a stream of lines (for example a LineStreamer), yielding the tokens of one line at a time.
+pub struct TokenizedLines<I, T> {
+    lines: I,
+    tokenizer: T,
+}
+
+
+impl<I: Iterator<Item = String>, T: Tokenizer> TokenizedLines<I, T> {
+    pub fn new(lines: I, tokenizer: T) -> TokenizedLines<I, T> {
+        TokenizedLines { lines: lines, tokenizer: tokenizer }
+    }
+}
+
+
+impl<I: Iterator<Item = String>, T: Tokenizer> Iterator for TokenizedLines<I, T> {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        self.lines.next().map(|line| self.tokenizer.tokenize(&line))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_tokenizers() {
+        let text = "  \"Well,\" she said (twice): it's 3.5km!  ";
+        assert_eq!(WhitespaceTokenizer.tokenize(text),
+                   vec!["\"Well,\"", "she", "said", "(twice):", "it's", "3.5km!"]);
+        assert_eq!(RegexTokenizer::new(r"\w+|[^\w\s]").unwrap().tokenize("it's 3km!"),
+                   vec!["it", "'", "s", "3km", "!"]);
+        assert_eq!(UnicodeWordTokenizer.tokenize(text), vec!["Well", "she", "said", "twice", "it's", "3.5km"]);
+        assert_eq!(PunctuationTokenizer.tokenize("(don't) stop"), vec!["(", "don", "'", "t", ")", "stop"]);
+        let lines = vec!["a b".to_string(), "".to_string(), "c".to_string()];
+        let tokenized: Vec<Vec<String>> = TokenizedLines::new(lines.into_iter(), WhitespaceTokenizer).collect();
+        assert_eq!(tokenized, vec![vec!["a", "b"], vec![], vec!["c"]]);
+    }
+}
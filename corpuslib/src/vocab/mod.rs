@@ -0,0 +1,3 @@
+pub mod vocabulary;
+
+pub use self::vocabulary::Vocabulary;
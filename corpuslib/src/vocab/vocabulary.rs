@@ -0,0 +1,133 @@
+// A frequency-thresholded vocabulary. Words are counted from a stream of lines, pruned by minimum frequency and/or
+// maximum size, and given ids in decreasing order of frequency; every other word maps to a single unknown token.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use corpus::stringmap::Stringmap;
+use tokenize::Tokenizer;
+
+
+pub const DEFAULT_UNKNOWN: &str = "<UNKNOWN>";
+
+
+#[derive(Clone)]
+pub struct Vocabulary {
+    // Id 0 is the unknown token; words follow from most to least frequent, ties broken alphabetically.
+    pub stringmap: Stringmap,
+    // Frequency of each id in the counted stream. The unknown token's frequency is the total of the pruned words.
+    pub counts: Vec<usize>,
+}
+
+
+impl Vocabulary {
+    // Counts the tokens of a stream of lines, as split (and normalized) by a tokenizer, and builds a vocabulary of the
+    // tokens occurring at least min_freq times, keeping at most max_size of them (not counting the unknown token) if
+    // given. Use the same tokenizer when mapping text through the vocabulary.
+    pub fn from_lines<I: Iterator<Item = String>>(lines: I, tokenizer: &dyn Tokenizer, min_freq: usize,
+                                                  max_size: Option<usize>, unknown: &str) -> Vocabulary {
+        let mut freqs: HashMap<String, usize> = HashMap::new();
+        for line in lines {
+            for word in tokenizer.tokenize(&line) {
+                let freq = freqs.entry(word).or_insert(0);
+                *freq += 1;
+            }
+        }
+        Vocabulary::from_counts(freqs, min_freq, max_size, unknown)
+    }
+
+    // Builds a vocabulary from word frequencies that have already been counted.
+    pub fn from_counts(mut freqs: HashMap<String, usize>, min_freq: usize, max_size: Option<usize>, unknown: &str)
+        -> Vocabulary {
+        // Occurrences of the unknown token itself (in text that was already pruned) count towards it.
+        let unknown_freq = freqs.remove(unknown).unwrap_or(0);
+        let mut ranked: Vec<(String, usize)> = freqs.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let mut keep = ranked.iter().take_while(|&&(_, freq)| freq >= min_freq).count();
+        if let Some(max_size) = max_size {
+            keep = cmp::min(keep, max_size);
+        }
+        let mut stringmap = Stringmap::new();
+        stringmap.add(unknown);
+        let pruned_freq: usize = ranked[keep..].iter().map(|&(_, freq)| freq).sum();
+        let mut counts: Vec<usize> = vec![unknown_freq + pruned_freq];
+        for &(ref word, freq) in ranked[..keep].iter() {
+            stringmap.add(word);
+            counts.push(freq);
+        }
+        Vocabulary { stringmap: stringmap, counts: counts }
+    }
+
+    // Returns the number of ids, including the unknown token.
+    pub fn len(&self) -> usize {
+        self.stringmap.code_ctr
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn unknown(&self) -> &str {
+        &self.stringmap.strings[0]
+    }
+
+    // Whether a word is in the vocabulary (the unknown token itself is not).
+    pub fn contains(&self, word: &str) -> bool {
+        match self.stringmap.get(word) {
+            Some(&code) => code > 0,
+            None => false,
+        }
+    }
+
+    // Returns the id of a word, or 0 (the unknown token) if it is out of vocabulary.
+    pub fn code(&self, word: &str) -> usize {
+        match self.stringmap.get(word) {
+            Some(&code) => code,
+            None => 0,
+        }
+    }
+
+    // Returns a word if it is in the vocabulary and the unknown token otherwise.
+    pub fn map<'a>(&'a self, word: &'a str) -> &'a str {
+        if self.contains(word) { word } else { self.unknown() }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokenize::{Normalization, Normalized, WhitespaceTokenizer};
+
+    #[test]
+    fn check_vocabulary() {
+        let lines = vec!["the cat sat on the mat".to_string(), "the dog sat".to_string()];
+        let v = Vocabulary::from_lines(lines.clone().into_iter(), &WhitespaceTokenizer, 2, None, DEFAULT_UNKNOWN);
+        assert_eq!(v.stringmap.strings, vec![DEFAULT_UNKNOWN, "the", "sat"]);
+        assert_eq!(v.counts, vec![4, 3, 2]);
+        assert_eq!(v.map("cat"), DEFAULT_UNKNOWN);
+        assert_eq!(v.map("sat"), "sat");
+        assert_eq!(v.code("the"), 1);
+        assert_eq!(v.code("dog"), 0);
+        assert!(!v.contains(DEFAULT_UNKNOWN));
+        let v = Vocabulary::from_lines(lines.into_iter(), &WhitespaceTokenizer, 1, Some(3), "<unk>");
+        assert_eq!(v.stringmap.strings, vec!["<unk>", "the", "sat", "cat"]);
+        assert_eq!(v.counts, vec![3, 3, 2, 1]);
+        // A literal unknown token in the input adds to the unknown count rather than being dropped.
+        let lines = vec!["the <unk> sat".to_string(), "the <unk> <unk> cat".to_string()];
+        let v = Vocabulary::from_lines(lines.into_iter(), &WhitespaceTokenizer, 2, None, "<unk>");
+        assert_eq!(v.stringmap.strings, vec!["<unk>", "the"]);
+        assert_eq!(v.counts, vec![5, 2]);
+    }
+
+    #[test]
+    fn counts_tokenizer_output() {
+        let lines = vec!["The cat sat.".to_string(), "the CAT ran".to_string()];
+        let lowercase = Normalization { lowercase: true, ..Normalization::default() };
+        let tokenizer = Normalized::new(WhitespaceTokenizer, lowercase);
+        let v = Vocabulary::from_lines(lines.into_iter(), &tokenizer, 2, None, "<unk>");
+        assert_eq!(v.stringmap.strings, vec!["<unk>", "cat", "the"]);
+        assert_eq!(v.counts, vec![2, 2, 2]);
+    }
+}